@@ -0,0 +1,151 @@
+use serde::Deserialize;
+
+use crate::AppError;
+use crate::totp::{Account, STEAM_ALGORITHM, normalize_algorithm};
+
+// Subset of the Aegis Authenticator backup format we need
+#[derive(Deserialize)]
+struct AegisBackup {
+    db: serde_json::Value,
+}
+
+#[derive(Deserialize)]
+struct AegisDatabase {
+    entries: Vec<AegisEntry>,
+}
+
+#[derive(Deserialize)]
+struct AegisEntry {
+    #[serde(rename = "type")]
+    entry_type: String,
+    name: String,
+    #[serde(default)]
+    issuer: String,
+    info: AegisInfo,
+}
+
+#[derive(Deserialize)]
+struct AegisInfo {
+    secret: String,
+    #[serde(default)]
+    algo: Option<String>,
+    #[serde(default)]
+    digits: Option<u32>,
+    #[serde(default)]
+    period: Option<u32>,
+}
+
+/// Parses an unencrypted Aegis JSON backup, returning the converted accounts
+/// and a description of each entry that was skipped.
+pub fn parse_aegis_backup(json: &str) -> Result<(Vec<Account>, Vec<String>), AppError> {
+    let backup: AegisBackup = serde_json::from_str(json)?;
+
+    // Encrypted vaults store the database as a base64 string
+    if backup.db.is_string() {
+        return Err(AppError::InvalidInput(
+            "Encrypted Aegis backups are not supported; export an unencrypted backup instead"
+                .to_string(),
+        ));
+    }
+    let db: AegisDatabase = serde_json::from_value(backup.db)?;
+
+    let mut accounts = Vec::new();
+    let mut skipped = Vec::new();
+    for entry in db.entries {
+        let algorithm = match entry.entry_type.as_str() {
+            "totp" => match entry.info.algo.as_deref().map(normalize_algorithm) {
+                None => "SHA1".to_string(),
+                Some(Some(algorithm)) => algorithm.to_string(),
+                Some(None) => {
+                    skipped.push(format!("{}: unsupported algorithm", entry.name));
+                    continue;
+                }
+            },
+            "steam" => STEAM_ALGORITHM.to_string(),
+            other => {
+                skipped.push(format!(
+                    "{}: {} accounts are not supported",
+                    entry.name, other
+                ));
+                continue;
+            }
+        };
+
+        let mut account = Account::new(entry.name, entry.info.secret);
+        if !entry.issuer.is_empty() {
+            account.issuer = entry.issuer;
+        }
+        account.algorithm = algorithm;
+        if let Some(digits) = entry.info.digits {
+            account.digits = digits;
+        }
+        if let Some(period) = entry.info.period {
+            account.period = period;
+        }
+        accounts.push(account);
+    }
+
+    Ok((accounts, skipped))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const SAMPLE_BACKUP: &str = r#"{
+        "version": 1,
+        "header": { "slots": null, "params": null },
+        "db": {
+            "version": 2,
+            "entries": [
+                {
+                    "type": "totp",
+                    "uuid": "01234567-89ab-cdef-0123-456789abcdef",
+                    "name": "alice@example.com",
+                    "issuer": "GitHub",
+                    "note": "",
+                    "icon": null,
+                    "info": { "secret": "JBSWY3DPEHPK3PXP", "algo": "SHA256", "digits": 8, "period": 60 }
+                },
+                {
+                    "type": "steam",
+                    "name": "gamer",
+                    "issuer": "Steam",
+                    "info": { "secret": "JBSWY3DPEHPK3PXP", "algo": "SHA1", "digits": 5, "period": 30 }
+                },
+                {
+                    "type": "hotp",
+                    "name": "counter",
+                    "issuer": "",
+                    "info": { "secret": "JBSWY3DPEHPK3PXP", "algo": "SHA1", "digits": 6, "counter": 3 }
+                }
+            ]
+        }
+    }"#;
+
+    #[test]
+    fn test_parse_aegis_backup() {
+        let (accounts, skipped) = parse_aegis_backup(SAMPLE_BACKUP).unwrap();
+
+        assert_eq!(accounts.len(), 2);
+        assert_eq!(skipped.len(), 1);
+        assert!(skipped[0].starts_with("counter"));
+
+        let github = &accounts[0];
+        assert_eq!(github.name, "alice@example.com");
+        assert_eq!(github.issuer, "GitHub");
+        assert_eq!(github.secret, "JBSWY3DPEHPK3PXP");
+        assert_eq!(github.algorithm, "SHA256");
+        assert_eq!(github.digits, 8);
+        assert_eq!(github.period, 60);
+
+        assert_eq!(accounts[1].algorithm, STEAM_ALGORITHM);
+    }
+
+    #[test]
+    fn test_parse_encrypted_aegis_backup() {
+        let json = r#"{"version": 1, "header": {"slots": [], "params": {}}, "db": "ZW5jcnlwdGVk"}"#;
+        let err = parse_aegis_backup(json).err().unwrap();
+        assert!(err.to_string().contains("Encrypted"));
+    }
+}
@@ -0,0 +1,142 @@
+use serde::Deserialize;
+
+use crate::AppError;
+use crate::totp::{Account, STEAM_ALGORITHM, normalize_algorithm};
+
+// Subset of an andOTP plain-text backup entry we need
+#[derive(Deserialize)]
+struct AndOtpEntry {
+    secret: String,
+    label: String,
+    #[serde(default)]
+    issuer: String,
+    #[serde(rename = "type")]
+    entry_type: String,
+    #[serde(default)]
+    algorithm: Option<String>,
+    #[serde(default)]
+    digits: Option<u32>,
+    #[serde(default)]
+    period: Option<u32>,
+}
+
+/// Parses a plain-text andOTP JSON backup, returning the converted accounts
+/// and a description of each entry that was skipped.
+pub fn parse_andotp_backup(contents: &[u8]) -> Result<(Vec<Account>, Vec<String>), AppError> {
+    // Plain backups are a JSON array; encrypted `.json.aes` ones are binary,
+    // starting with the key derivation parameters
+    if contents.trim_ascii_start().first() != Some(&b'[') {
+        return Err(AppError::InvalidInput(
+            "Encrypted andOTP backups are not supported; create a plain-text backup instead"
+                .to_string(),
+        ));
+    }
+    let entries: Vec<AndOtpEntry> = serde_json::from_slice(contents)?;
+
+    let mut accounts = Vec::new();
+    let mut skipped = Vec::new();
+    for entry in entries {
+        let algorithm = match entry.entry_type.to_ascii_lowercase().as_str() {
+            "totp" => match entry.algorithm.as_deref().map(normalize_algorithm) {
+                None => "SHA1".to_string(),
+                Some(Some(algorithm)) => algorithm.to_string(),
+                Some(None) => {
+                    skipped.push(format!("{}: unsupported algorithm", entry.label));
+                    continue;
+                }
+            },
+            "steam" => STEAM_ALGORITHM.to_string(),
+            other => {
+                skipped.push(format!(
+                    "{}: {} accounts are not supported",
+                    entry.label, other
+                ));
+                continue;
+            }
+        };
+
+        let mut account = Account::new(entry.label, entry.secret);
+        if !entry.issuer.is_empty() {
+            account.issuer = entry.issuer;
+        }
+        account.algorithm = algorithm;
+        if let Some(digits) = entry.digits {
+            account.digits = digits;
+        }
+        if let Some(period) = entry.period {
+            account.period = period;
+        }
+        accounts.push(account);
+    }
+
+    Ok((accounts, skipped))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const SAMPLE_BACKUP: &str = r#"[
+        {
+            "secret": "JBSWY3DPEHPK3PXP",
+            "issuer": "GitHub",
+            "label": "alice@example.com",
+            "digits": 8,
+            "type": "TOTP",
+            "algorithm": "SHA256",
+            "thumbnail": "Github",
+            "last_used": 1700000000000,
+            "used_frequency": 3,
+            "period": 60,
+            "tags": ["work"]
+        },
+        {
+            "secret": "JBSWY3DPEHPK3PXP",
+            "issuer": "Steam",
+            "label": "gamer",
+            "digits": 5,
+            "type": "STEAM",
+            "algorithm": "SHA1",
+            "period": 30,
+            "tags": []
+        },
+        {
+            "secret": "JBSWY3DPEHPK3PXP",
+            "issuer": "",
+            "label": "counter",
+            "digits": 6,
+            "type": "HOTP",
+            "algorithm": "SHA1",
+            "counter": 3,
+            "tags": []
+        }
+    ]"#;
+
+    #[test]
+    fn test_parse_andotp_backup() {
+        let (accounts, skipped) = parse_andotp_backup(SAMPLE_BACKUP.as_bytes()).unwrap();
+
+        assert_eq!(accounts.len(), 2);
+        assert_eq!(skipped.len(), 1);
+        assert!(skipped[0].starts_with("counter"));
+
+        let github = &accounts[0];
+        assert_eq!(github.name, "alice@example.com");
+        assert_eq!(github.issuer, "GitHub");
+        assert_eq!(github.secret, "JBSWY3DPEHPK3PXP");
+        assert_eq!(github.algorithm, "SHA256");
+        assert_eq!(github.digits, 8);
+        assert_eq!(github.period, 60);
+
+        assert_eq!(accounts[1].algorithm, STEAM_ALGORITHM);
+        assert_eq!(accounts[1].digits, 5);
+    }
+
+    #[test]
+    fn test_parse_encrypted_andotp_backup() {
+        // Iterations, salt and nonce come before the ciphertext
+        let contents = [0, 0, 0x03, 0xe8, 0x9f, 0x12, 0xc4, 0x7a, 0x01];
+        let err = parse_andotp_backup(&contents).err().unwrap();
+        assert!(err.to_string().contains("Encrypted"));
+    }
+}
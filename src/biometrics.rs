@@ -0,0 +1,51 @@
+/// Asks the user to authenticate with Touch ID through macOS's
+/// LocalAuthentication framework, showing `reason` in the system prompt.
+/// Returns false if biometrics are unavailable, fail or are cancelled, in
+/// which case the caller should ask for the passphrase.
+#[cfg(all(target_os = "macos", feature = "macos-biometrics"))]
+pub fn authenticate(reason: &str) -> bool {
+    use std::sync::mpsc;
+
+    use block2::RcBlock;
+    use objc2::rc::Retained;
+    use objc2::runtime::{AnyObject, Bool};
+    use objc2::{class, msg_send};
+    use objc2_foundation::NSString;
+
+    #[link(name = "LocalAuthentication", kind = "framework")]
+    unsafe extern "C" {}
+
+    // LAPolicyDeviceOwnerAuthenticationWithBiometrics
+    const POLICY: isize = 1;
+
+    // SAFETY: LAContext is sent the messages its documentation describes,
+    // with arguments of the documented types
+    unsafe {
+        let context: Retained<AnyObject> = msg_send![class!(LAContext), new];
+        let no_error = std::ptr::null_mut::<*mut AnyObject>();
+        let available: Bool = msg_send![&context, canEvaluatePolicy: POLICY, error: no_error];
+        if !available.as_bool() {
+            return false;
+        }
+
+        // The reply arrives on another queue, so wait for it here
+        let (sender, receiver) = mpsc::channel();
+        let reply = RcBlock::new(move |success: Bool, _error: *mut AnyObject| {
+            let _ = sender.send(success.as_bool());
+        });
+        let reason = NSString::from_str(reason);
+        let _: () = msg_send![
+            &context,
+            evaluatePolicy: POLICY,
+            localizedReason: &*reason,
+            reply: &*reply
+        ];
+        receiver.recv().unwrap_or(false)
+    }
+}
+
+// Other platforms, and builds without `macos-biometrics`, use the passphrase
+#[cfg(not(all(target_os = "macos", feature = "macos-biometrics")))]
+pub fn authenticate(_reason: &str) -> bool {
+    false
+}
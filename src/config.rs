@@ -0,0 +1,149 @@
+use std::env;
+use std::fs;
+use std::io::ErrorKind;
+use std::path::{Path, PathBuf};
+
+use directories::BaseDirs;
+use serde::Deserialize;
+
+use crate::{AppError, REFRESH_MS_RANGE, parse_hash_algorithm, profile};
+
+const CONFIG_FILE: &str = "config.toml";
+const CONFIG_ENV: &str = "HOTPOT_CONFIG";
+
+/// Defaults from `config.toml`. Each one gives way to its command-line flag
+/// or environment variable.
+#[derive(Debug, Default, Deserialize, PartialEq)]
+#[serde(default, deny_unknown_fields)]
+pub struct Config {
+    /// File storage used when neither --file nor HOTPOT_FILE is given
+    pub default_file: Option<PathBuf>,
+    pub default_profile: Option<String>,
+    pub clipboard_clear_seconds: Option<u64>,
+    /// Dashboard refresh interval in milliseconds, like --refresh-ms
+    pub refresh_ms: Option<u64>,
+    /// Theme file used instead of `theme.toml`
+    pub theme: Option<PathBuf>,
+    /// Algorithm for accounts added without --algorithm
+    pub default_algorithm: Option<String>,
+}
+
+impl Config {
+    /// Loads the file named by HOTPOT_CONFIG, or else `config.toml` in the
+    /// config directory, which doesn't have to exist.
+    pub fn load() -> Result<Self, AppError> {
+        let (path, required) = match env::var_os(CONFIG_ENV) {
+            Some(path) if !path.is_empty() => (PathBuf::from(path), true),
+            _ => (profile::config_dir()?.join(CONFIG_FILE), false),
+        };
+        let contents = match fs::read_to_string(&path) {
+            Ok(contents) => contents,
+            Err(e) if e.kind() == ErrorKind::NotFound && !required => return Ok(Self::default()),
+            Err(e) => {
+                return Err(AppError::Storage(format!(
+                    "Failed to read {}: {}",
+                    path.display(),
+                    e
+                )));
+            }
+        };
+        let mut config = Self::parse(&contents).map_err(|e| {
+            AppError::InvalidInput(format!("Invalid config in {}: {}", path.display(), e))
+        })?;
+
+        // Relative paths don't depend on where hotpot is run from
+        let dir = path.parent().unwrap_or(Path::new(""));
+        for file in [&mut config.default_file, &mut config.theme]
+            .into_iter()
+            .flatten()
+        {
+            *file = resolve_path(dir, file);
+        }
+        Ok(config)
+    }
+
+    fn parse(contents: &str) -> Result<Self, String> {
+        let mut config: Self = toml::from_str(contents).map_err(|e| e.message().to_string())?;
+        if let Some(algorithm) = &config.default_algorithm {
+            let algorithm =
+                parse_hash_algorithm(algorithm).map_err(|e| format!("default_algorithm: {}", e))?;
+            config.default_algorithm = Some(algorithm);
+        }
+        if let Some(refresh_ms) = config.refresh_ms {
+            if !REFRESH_MS_RANGE.contains(&refresh_ms) {
+                return Err(format!(
+                    "refresh_ms: {} is not in {}..={}",
+                    refresh_ms,
+                    REFRESH_MS_RANGE.start(),
+                    REFRESH_MS_RANGE.end()
+                ));
+            }
+        }
+        Ok(config)
+    }
+}
+
+/// Expands a leading `~` to the home directory and makes other relative
+/// paths relative to `dir`, the one holding the config file.
+fn resolve_path(dir: &Path, path: &Path) -> PathBuf {
+    if let Ok(rest) = path.strip_prefix("~") {
+        if let Some(dirs) = BaseDirs::new() {
+            return dirs.home_dir().join(rest);
+        }
+    }
+    dir.join(path)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_config() {
+        let config = Config::parse(
+            r#"
+            default_file = "accounts.json"
+            default_profile = "work"
+            clipboard_clear_seconds = 60
+            refresh_ms = 1000
+            default_algorithm = "sha-256"
+            "#,
+        )
+        .unwrap();
+
+        assert_eq!(config.default_file, Some(PathBuf::from("accounts.json")));
+        assert_eq!(config.default_profile.as_deref(), Some("work"));
+        assert_eq!(config.clipboard_clear_seconds, Some(60));
+        assert_eq!(config.refresh_ms, Some(1000));
+        assert_eq!(config.default_algorithm.as_deref(), Some("SHA256"));
+        // Settings that aren't given are left to the flags and built-in defaults
+        assert_eq!(config.theme, None);
+        assert_eq!(Config::parse("").unwrap(), Config::default());
+    }
+
+    #[test]
+    fn test_parse_config_rejects_bad_values() {
+        assert!(Config::parse(r#"default_algorithm = "MD5""#).is_err());
+        assert!(Config::parse("clipboard_clear_seconds = -1").is_err());
+        assert!(Config::parse("refresh_ms = 10").is_err());
+        assert!(Config::parse(r#"default_fiel = "accounts.json""#).is_err());
+    }
+
+    #[test]
+    fn test_resolve_path() {
+        let dir = Path::new("/etc/hotpot");
+        assert_eq!(
+            resolve_path(dir, Path::new("theme.toml")),
+            Path::new("/etc/hotpot/theme.toml")
+        );
+        assert_eq!(
+            resolve_path(dir, Path::new("/tmp/accounts.json")),
+            Path::new("/tmp/accounts.json")
+        );
+        let home = BaseDirs::new().unwrap().home_dir().to_path_buf();
+        assert_eq!(
+            resolve_path(dir, Path::new("~/accounts.json")),
+            home.join("accounts.json")
+        );
+    }
+}
@@ -0,0 +1,210 @@
+use zeroize::Zeroizing;
+
+use crate::AppError;
+use crate::totp::Account;
+
+const COLUMNS: [&str; 5] = ["name", "issuer", "algorithm", "digits", "period"];
+
+/// Renders `accounts` as CSV with a header row, for auditing or spreadsheets.
+/// Secrets are left out unless `include_secrets` is set, in which case they
+/// are added in Base32 as a final `secret` column.
+pub fn write_accounts_csv(
+    accounts: &[Account],
+    include_secrets: bool,
+) -> Result<Zeroizing<Vec<u8>>, AppError> {
+    let to_error = |e: csv::Error| AppError::Storage(format!("Failed to write CSV: {}", e));
+    let mut writer = csv::Writer::from_writer(Vec::new());
+
+    let mut header = COLUMNS.to_vec();
+    if include_secrets {
+        header.push("secret");
+    }
+    writer.write_record(&header).map_err(to_error)?;
+
+    for account in accounts {
+        let digits = account.digits.to_string();
+        let period = account.period.to_string();
+        let secret = account.base32_secret();
+        let mut record = vec![
+            account.name.as_str(),
+            &account.issuer,
+            &account.algorithm,
+            &digits,
+            &period,
+        ];
+        if include_secrets {
+            record.push(secret.as_str());
+        }
+        writer.write_record(&record).map_err(to_error)?;
+    }
+
+    writer
+        .into_inner()
+        .map(Zeroizing::new)
+        .map_err(|e| AppError::Storage(format!("Failed to write CSV: {}", e.error())))
+}
+
+/// Parses CSV with a header row naming its columns, in any order: `name`
+/// and `secret` are required, while `issuer`, `algorithm`, `digits` and
+/// `period` fall back to the usual defaults when missing or empty.
+///
+/// Returns the accounts along with a description, by line number, of each
+/// row that couldn't be used.
+pub fn parse_accounts_csv(data: &str) -> Result<(Vec<Account>, Vec<String>), AppError> {
+    let mut reader = csv::ReaderBuilder::new()
+        .flexible(true)
+        .trim(csv::Trim::All)
+        .from_reader(data.as_bytes());
+    let headers = reader
+        .headers()
+        .map_err(|e| AppError::InvalidInput(format!("Failed to read CSV header: {}", e)))?
+        .clone();
+    let column = |name: &str| {
+        headers
+            .iter()
+            .position(|header| header.eq_ignore_ascii_case(name))
+    };
+    let (Some(name_column), Some(secret_column)) = (column("name"), column("secret")) else {
+        return Err(AppError::InvalidInput(
+            "CSV needs a header row with 'name' and 'secret' columns".to_string(),
+        ));
+    };
+    let issuer_column = column("issuer");
+    let algorithm_column = column("algorithm");
+    let digits_column = column("digits");
+    let period_column = column("period");
+
+    let mut accounts = Vec::new();
+    let mut skipped = Vec::new();
+    for result in reader.records() {
+        let record = match result {
+            Ok(record) => record,
+            Err(e) => {
+                let line = e.position().map_or(0, |position| position.line());
+                skipped.push(format!("line {}: {}", line, e));
+                continue;
+            }
+        };
+        let line = record.position().map_or(0, |position| position.line());
+        let field = |column: Option<usize>| {
+            column
+                .and_then(|column| record.get(column))
+                .filter(|value| !value.is_empty())
+        };
+
+        let Some(name) = field(Some(name_column)) else {
+            skipped.push(format!("line {}: missing name", line));
+            continue;
+        };
+        let secret = Zeroizing::new(field(Some(secret_column)).unwrap_or_default().to_string());
+        let mut builder = Account::builder(name, secret.as_str());
+        if let Some(issuer) = field(issuer_column) {
+            builder = builder.issuer(issuer);
+        }
+        if let Some(algorithm) = field(algorithm_column) {
+            builder = builder.algorithm(algorithm);
+        }
+        let number = |column: Option<usize>, label: &str| match field(column) {
+            None => Ok(None),
+            Some(value) => value
+                .parse::<u32>()
+                .map(Some)
+                .map_err(|_| format!("invalid {} '{}'", label, value)),
+        };
+        let settings = number(digits_column, "digits")
+            .and_then(|digits| Ok((digits, number(period_column, "period")?)));
+        let (digits, period) = match settings {
+            Ok(settings) => settings,
+            Err(reason) => {
+                skipped.push(format!("line {}: {}", line, reason));
+                continue;
+            }
+        };
+        if let Some(digits) = digits {
+            builder = builder.digits(digits);
+        }
+        if let Some(period) = period {
+            builder = builder.period(period);
+        }
+
+        match builder.build() {
+            Ok(account) => accounts.push(account),
+            Err(e) => skipped.push(format!("line {} ('{}'): {}", line, name, e)),
+        }
+    }
+
+    Ok((accounts, skipped))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_accounts_csv() {
+        let csv = "name,issuer,secret,algorithm,digits,period\n\
+                   github,GitHub,jbsw y3dp ehpk 3pxp,,,\n\
+                   \"Smith, Jane\",Bank,GEZDGNBVGY3TQOJQ,sha256,8,60\n";
+        let (accounts, skipped) = parse_accounts_csv(csv).unwrap();
+
+        assert!(skipped.is_empty());
+        assert_eq!(accounts.len(), 2);
+        assert_eq!(accounts[0].name, "github");
+        assert_eq!(accounts[0].issuer, "GitHub");
+        assert_eq!(accounts[0].secret, "JBSWY3DPEHPK3PXP");
+        assert_eq!(accounts[0].algorithm, "SHA1");
+        assert_eq!((accounts[0].digits, accounts[0].period), (6, 30));
+        assert_eq!(accounts[1].name, "Smith, Jane");
+        assert_eq!(accounts[1].algorithm, "SHA256");
+        assert_eq!((accounts[1].digits, accounts[1].period), (8, 60));
+    }
+
+    #[test]
+    fn test_parse_accounts_csv_reports_bad_rows() {
+        // Columns can come in any order, and trailing ones can be left off
+        let csv = "secret,name,digits\n\
+                   JBSWY3DPEHPK3PXP,github\n\
+                   not base32!,google,6\n\
+                   JBSWY3DPEHPK3PXP,gitlab,six\n\
+                   JBSWY3DPEHPK3PXP,,6\n";
+        let (accounts, skipped) = parse_accounts_csv(csv).unwrap();
+
+        assert_eq!(accounts.len(), 1);
+        assert_eq!(accounts[0].name, "github");
+        assert_eq!(
+            skipped,
+            [
+                "line 3 ('google'): Secret is not valid Base32",
+                "line 4: invalid digits 'six'",
+                "line 5: missing name",
+            ]
+        );
+
+        assert!(parse_accounts_csv("issuer,secret\nGitHub,JBSWY3DPEHPK3PXP\n").is_err());
+    }
+
+    #[test]
+    fn test_write_accounts_csv() {
+        let mut quoted = Account::new("a, \"b\"".to_string(), "JBSWY3DPEHPK3PXP".to_string());
+        quoted.issuer = "Acme, Inc.".to_string();
+        let accounts = [
+            Account::new("github".to_string(), "JBSWY3DPEHPK3PXP".to_string()),
+            quoted,
+        ];
+
+        let csv = write_accounts_csv(&accounts, false).unwrap();
+        assert_eq!(
+            String::from_utf8(csv.to_vec()).unwrap(),
+            "name,issuer,algorithm,digits,period\n\
+             github,hotpot,SHA1,6,30\n\
+             \"a, \"\"b\"\"\",\"Acme, Inc.\",SHA1,6,30\n"
+        );
+
+        let csv = write_accounts_csv(&accounts[..1], true).unwrap();
+        assert_eq!(
+            String::from_utf8(csv.to_vec()).unwrap(),
+            "name,issuer,algorithm,digits,period,secret\n\
+             github,hotpot,SHA1,6,30,JBSWY3DPEHPK3PXP\n"
+        );
+    }
+}
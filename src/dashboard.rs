@@ -1,8 +1,9 @@
 use std::{
     cmp::min,
-    collections::HashMap,
-    io::{self, Write},
-    time::{Duration, SystemTime, UNIX_EPOCH},
+    collections::{HashMap, HashSet, hash_map::Entry},
+    io::{self, IsTerminal, Write},
+    ops::Range,
+    time::{Duration, Instant, SystemTime, UNIX_EPOCH},
 };
 
 use arboard::Clipboard;
@@ -16,21 +17,45 @@ use crossterm::{
 use fuzzy_matcher::FuzzyMatcher;
 use fuzzy_matcher::skim::SkimMatcherV2;
 use rpassword::prompt_password;
-
-use crate::{AppError, delete_account, get_storage, save_account, totp::generate_totp};
+use unicode_width::{UnicodeWidthChar, UnicodeWidthStr};
+use zeroize::{Zeroize, Zeroizing};
+
+use crate::{
+    AppError, confirm_secret_length, delete_accounts, rank_accounts, save_account,
+    storage::{StorageBackend, get_storage},
+    theme::Theme,
+    totp::{
+        Account, DEFAULT_ISSUER, MAX_DIGITS, MIN_DIGITS, TotpCode, decode_secret,
+        generate_totp_full, generate_totp_with_key, normalize_algorithm,
+    },
+    update_account, warn_issuer_mismatch,
+};
 
 // Screen buffer for double buffering
 struct ScreenBuffer {
     lines: Vec<BufferLine>,
+    /// What the terminal currently shows, or `None` if something other than
+    /// this buffer has drawn on it and every line must be repainted
+    drawn: Option<Vec<BufferLine>>,
     width: u16,
     height: u16,
 }
 
-#[derive(Clone)]
+#[derive(Clone, PartialEq)]
 struct BufferLine {
     content: String,
     is_highlighted: bool,
     copied_split_pos: Option<usize>, // Position where "copied" text starts for special rendering
+    dim_ranges: Vec<Range<usize>>,   // Byte ranges drawn in a dimmer style
+    match_ranges: Vec<Range<usize>>, // Byte ranges underlined as search matches
+    is_progress: bool,               // Drawn in the theme's progress bar color
+}
+
+// A revealed secret passes through these lines, so none are left in memory
+impl Drop for BufferLine {
+    fn drop(&mut self) {
+        self.content.zeroize();
+    }
 }
 
 impl ScreenBuffer {
@@ -41,19 +66,64 @@ impl ScreenBuffer {
                     content: String::new(),
                     is_highlighted: false,
                     copied_split_pos: None,
+                    dim_ranges: Vec::new(),
+                    match_ranges: Vec::new(),
+                    is_progress: false,
                 };
                 height as usize
             ],
+            drawn: None,
             width,
             height,
         }
     }
 
+    /// Rows available for the account list below the header and progress bar.
+    fn account_rows(&self) -> usize {
+        rows_below_header(self.height)
+    }
+
+    /// Draws the dimmed status line on the bottom row, when the terminal is
+    /// tall enough to have one below the progress bar.
+    fn render_status(&mut self, shown: usize, total: usize) {
+        if self.height < 5 {
+            return;
+        }
+        let row = self.height - 1;
+        let line = status_line(shown, total, self.account_rows());
+        let len = line.len();
+        self.write_line(row, line);
+        self.dim_range(row, 0..len);
+    }
+
+    /// Shows a revealed secret on the status line in place of the counts.
+    fn render_revealed_secret(&mut self, revealed: &RevealedSecret, now: Instant) {
+        if self.height < 5 {
+            return;
+        }
+        self.write_line(self.height - 1, revealed.status_line(now));
+    }
+
     fn clear(&mut self) {
         for line in &mut self.lines {
-            line.content.clear();
+            line.content.zeroize();
             line.is_highlighted = false;
             line.copied_split_pos = None;
+            line.dim_ranges.clear();
+            line.match_ranges.clear();
+            line.is_progress = false;
+        }
+    }
+
+    fn dim_range(&mut self, row: u16, range: Range<usize>) {
+        if row < self.height {
+            self.lines[row as usize].dim_ranges.push(range);
+        }
+    }
+
+    fn underline_range(&mut self, row: u16, range: Range<usize>) {
+        if row < self.height {
+            self.lines[row as usize].match_ranges.push(range);
         }
     }
 
@@ -83,15 +153,38 @@ impl ScreenBuffer {
         }
     }
 
-    fn flush_to_screen(&self, stdout: &mut io::Stdout) -> Result<(), AppError> {
-        queue!(stdout, MoveTo(0, 0), Clear(ClearType::All))?;
-        for (row, line) in self.lines.iter().enumerate() {
+    /// Forgets what is on screen, so the next flush repaints everything.
+    fn invalidate(&mut self) {
+        self.drawn = None;
+    }
+
+    /// Rows whose content or style differs from what was last flushed.
+    fn changed_rows(&self) -> Vec<usize> {
+        (0..self.lines.len())
+            .filter(|&row| {
+                self.drawn
+                    .as_ref()
+                    .is_none_or(|drawn| drawn.get(row) != Some(&self.lines[row]))
+            })
+            .collect()
+    }
+
+    /// Redraws only the lines that changed since the last frame, which keeps
+    /// the dashboard from flickering on slow terminals and SSH links.
+    fn flush_to_screen(&mut self, stdout: &mut io::Stdout, theme: &Theme) -> Result<(), AppError> {
+        if self.drawn.is_none() {
+            queue!(stdout, MoveTo(0, 0), Clear(ClearType::All))?;
+        }
+        for row in self.changed_rows() {
+            let line = &self.lines[row];
+            queue!(stdout, MoveTo(0, row as u16))?;
             if !line.content.is_empty() {
-                queue!(stdout, MoveTo(0, row as u16))?;
-                self.render_line_content(stdout, line)?;
+                self.render_line_content(stdout, line, theme)?;
             }
+            queue!(stdout, Clear(ClearType::UntilNewLine))?;
         }
         stdout.flush()?;
+        self.drawn = Some(self.lines.clone());
         Ok(())
     }
 
@@ -99,35 +192,63 @@ impl ScreenBuffer {
         &self,
         stdout: &mut io::Stdout,
         line: &BufferLine,
+        theme: &Theme,
     ) -> Result<(), AppError> {
-        if line.is_highlighted {
+        if !theme.styled {
+            self.render_plain_content(stdout, line)
+        } else if line.is_highlighted {
             if let Some(split_pos) = line.copied_split_pos {
-                self.render_highlighted_with_copied(stdout, &line.content, split_pos)
+                self.render_highlighted_with_copied(stdout, line, split_pos, theme)
             } else {
-                self.render_highlighted_content(stdout, &line.content)
+                self.render_highlighted_content(stdout, line, theme)
             }
+        } else if line.is_progress {
+            queue!(
+                stdout,
+                SetForegroundColor(theme.progress),
+                Print(&line.content),
+                SetForegroundColor(Color::Reset)
+            )?;
+            Ok(())
         } else {
-            self.render_normal_content(stdout, &line.content)
+            self.render_normal_content(stdout, line)
+        }
+    }
+
+    // Without styling the selected row is marked in its left padding instead
+    fn render_plain_content(
+        &self,
+        stdout: &mut io::Stdout,
+        line: &BufferLine,
+    ) -> Result<(), AppError> {
+        match line.content.strip_prefix(' ') {
+            Some(rest) if line.is_highlighted => queue!(stdout, Print('>'), Print(rest))?,
+            _ => queue!(stdout, Print(&line.content))?,
         }
+        Ok(())
     }
 
     fn render_highlighted_with_copied(
         &self,
         stdout: &mut io::Stdout,
-        content: &str,
+        line: &BufferLine,
         split_pos: usize,
+        theme: &Theme,
     ) -> Result<(), AppError> {
-        let (highlighted_part, copied_part) = content.split_at(split_pos);
+        let (highlighted_part, copied_part) = line.content.split_at(split_pos);
         queue!(
             stdout,
             SetAttribute(Attribute::Bold),
-            SetForegroundColor(Color::Black),
-            SetBackgroundColor(Color::White),
-            Print(highlighted_part),
+            SetForegroundColor(theme.highlight_fg),
+            SetBackgroundColor(theme.highlight_bg)
+        )?;
+        self.print_with_styled_ranges(stdout, highlighted_part, line, true)?;
+        queue!(
+            stdout,
             SetAttribute(Attribute::Reset),
             SetForegroundColor(Color::Reset),
             SetBackgroundColor(Color::Reset),
-            SetForegroundColor(Color::Green),
+            SetForegroundColor(theme.copied),
             Print(copied_part),
             SetForegroundColor(Color::Reset)
         )?;
@@ -137,14 +258,18 @@ impl ScreenBuffer {
     fn render_highlighted_content(
         &self,
         stdout: &mut io::Stdout,
-        content: &str,
+        line: &BufferLine,
+        theme: &Theme,
     ) -> Result<(), AppError> {
         queue!(
             stdout,
             SetAttribute(Attribute::Bold),
-            SetForegroundColor(Color::Black),
-            SetBackgroundColor(Color::White),
-            Print(content),
+            SetForegroundColor(theme.highlight_fg),
+            SetBackgroundColor(theme.highlight_bg)
+        )?;
+        self.print_with_styled_ranges(stdout, &line.content, line, true)?;
+        queue!(
+            stdout,
             SetAttribute(Attribute::Reset),
             SetForegroundColor(Color::Reset),
             SetBackgroundColor(Color::Reset)
@@ -153,77 +278,145 @@ impl ScreenBuffer {
     }
 
     fn render_normal_content(
+        &self,
+        stdout: &mut io::Stdout,
+        line: &BufferLine,
+    ) -> Result<(), AppError> {
+        self.print_with_styled_ranges(stdout, &line.content, line, false)
+    }
+
+    /// Prints `content`, which is all or the start of `line`, dimming and
+    /// underlining the line's byte ranges. Ranges may extend past the end of
+    /// `content` but must not overlap.
+    fn print_with_styled_ranges(
         &self,
         stdout: &mut io::Stdout,
         content: &str,
+        line: &BufferLine,
+        bold: bool,
     ) -> Result<(), AppError> {
-        queue!(stdout, Print(content))?;
+        let mut ranges: Vec<(&Range<usize>, bool)> = line
+            .dim_ranges
+            .iter()
+            .map(|range| (range, true))
+            .chain(line.match_ranges.iter().map(|range| (range, false)))
+            .collect();
+        ranges.sort_by_key(|(range, _)| range.start);
+
+        let mut pos = 0;
+        for (range, dim) in ranges {
+            let start = range.start.clamp(pos, content.len());
+            let end = range.end.clamp(start, content.len());
+            if start == end {
+                continue;
+            }
+            let (on, off) = if dim {
+                (Attribute::Dim, Attribute::NormalIntensity)
+            } else {
+                (Attribute::Underlined, Attribute::NoUnderline)
+            };
+            queue!(
+                stdout,
+                Print(&content[pos..start]),
+                SetAttribute(on),
+                Print(&content[start..end]),
+                SetAttribute(off)
+            )?;
+            // NormalIntensity clears bold as well as dim
+            if dim && bold {
+                queue!(stdout, SetAttribute(Attribute::Bold))?;
+            }
+            pos = end;
+        }
+        queue!(stdout, Print(&content[pos..]))?;
         Ok(())
     }
 
     fn render_header(&mut self, mode: &DashboardMode, name_buffer: &str) {
         let header = match mode {
-            DashboardMode::List => "[F]ind [A]dd [D]elete [E]xport QR [Q]uit".to_string(),
+            DashboardMode::List => {
+                "[F]ind [A]dd [M]odify [D]elete [E]xport QR [Q]uit [?] Help".to_string()
+            }
+            DashboardMode::Help => "Help (press any key to go back)".to_string(),
             DashboardMode::Search(query) => format!("Search (ESC to exit): {}_", query),
             DashboardMode::Add => format!("Enter account name (ESC to cancel): {}_", name_buffer),
             DashboardMode::AddMethod => {
-                if cfg!(target_os = "macos") {
-                    "Choose add method: [S]creenshot [M]anual (ESC to cancel)".to_string()
+                if cfg!(any(target_os = "macos", target_os = "linux", windows)) {
+                    "Choose add method: [S]creenshot [I]mage [P]aste [M]anual (ESC to cancel)"
+                        .to_string()
                 } else {
-                    "Choose add method: [M]anual (ESC to cancel)".to_string()
+                    "Choose add method: [I]mage [P]aste [M]anual (ESC to cancel)".to_string()
                 }
             }
         };
         self.write_line(0, header);
     }
 
-    fn render_progress_bar(&mut self) {
-        let now = SystemTime::now()
-            .duration_since(UNIX_EPOCH)
-            .expect("Time went backwards");
-        let secs_until_next_30 = 30 - (now.as_secs() % 30);
-        let subsec_progress = now.as_millis() % 1000;
-
-        let bar_width = 30;
-        let total_progress = (30 - secs_until_next_30) as f64 + (subsec_progress as f64 / 1000.0);
-        let filled_exact = total_progress;
-        let filled_full = filled_exact.floor() as usize;
-        let partial = filled_exact - filled_full as f64;
-
-        let blocks = ["", "▏", "▎", "▍", "▌", "▋", "▊", "▉", "█"];
-        let partial_block = if filled_full < bar_width {
-            blocks[(partial * 8.0) as usize]
-        } else {
-            ""
-        };
-
-        let remaining = if filled_full < bar_width {
-            bar_width - filled_full - if partial_block.is_empty() { 0 } else { 1 }
+    /// Lists every key the dashboard responds to below the header.
+    fn render_help(&mut self) {
+        let add = if cfg!(any(target_os = "macos", target_os = "linux", windows)) {
+            "Add from a [S]creenshot, [I]mage, [P]asted image or [M]anually"
         } else {
-            0
+            "Add from an [I]mage, [P]asted image or [M]anually"
         };
+        let keys = [
+            ("Up/Down", "Move the selection"),
+            ("Enter", "Copy the selected code to the clipboard"),
+            ("F", "Find accounts by fuzzy-matching their names"),
+            ("A", add),
+            ("M", "Modify the issuer, algorithm, digits or period"),
+            ("D", "Delete the selected or marked accounts, if confirmed"),
+            ("Space", "Mark or unmark the selected account"),
+            ("E", "Export the selected account as a QR code"),
+            ("I", "Show or hide issuers"),
+            ("N", "Show or hide the next code"),
+            ("P", "Pin the selected account to the top, or unpin it"),
+            ("Shift-R", "Show the selected secret briefly, if confirmed"),
+            ("?", "Show this help"),
+            ("Esc", "Leave search or add, clear marks, or quit"),
+            ("Q, Ctrl-C", "Quit"),
+        ];
+        for (row, (key, action)) in keys.into_iter().enumerate() {
+            let row = 2 + row as u16;
+            self.write_line(row, format!(" {:<11}{}", key, action));
+            self.dim_range(row, 1..1 + key.len());
+        }
+    }
 
-        let progress_line = format!(
-            "|{}{}{}| {:2}s",
-            "█".repeat(filled_full),
-            partial_block,
-            " ".repeat(remaining),
-            secs_until_next_30
-        );
-        self.write_line(2, progress_line);
+    /// Draws the countdown for the selected account's time step, falling back
+    /// to the standard 30-second period when nothing is selected.
+    fn render_progress_bar(&mut self, account: Option<&Account>) {
+        let now = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .expect("Time went backwards");
+        let (period, epoch, now) = account.map_or((DEFAULT_PERIOD, 0, now), |a| {
+            (a.period, a.epoch, a.adjusted_time(now))
+        });
+        self.write_line(2, progress_line(period, epoch, now));
+        if let Some(line) = self.lines.get_mut(2) {
+            line.is_progress = true;
+        }
     }
 
+    /// Draws an account's name and code. `matched` holds the character
+    /// positions in the name that matched the search, which are underlined,
+    /// and `marked` accounts get a check mark for bulk deletion.
+    #[allow(clippy::too_many_arguments)]
     fn render_account_line(
         &mut self,
         account: &crate::totp::Account,
         row: u16,
         selected: bool,
+        marked: bool,
         copied_state: &CopiedState,
+        display: &DisplayOptions,
+        codes: &mut CodeCache,
+        matched: &[usize],
     ) -> Result<(), AppError> {
         let now = SystemTime::now()
             .duration_since(UNIX_EPOCH)
             .expect("Time went backwards");
-        let code = generate_totp(account, now)?;
+        let totp = codes.generate(account, now)?;
 
         let max_width = min(self.width, 64);
         let copied_text = "  Copied to clipboard!";
@@ -234,20 +427,40 @@ impl ScreenBuffer {
         };
 
         // Always reserve space for " Copied!" to keep codes aligned
-        let code_str = format!("{:0width$}", code, width = account.digits as usize);
+        let mut code_str = totp.display(display.group_codes);
+        // The next window's code is shown after the current one, separated by a space
+        let mut next_code_len = 0;
+        if display.show_next {
+            let next_str = codes
+                .generate(account, totp.valid_until)?
+                .display(display.group_codes);
+            next_code_len = next_str.len();
+            code_str = format!("{} {}", code_str, next_str);
+        }
         let max_name_len =
             (max_width as usize).saturating_sub(code_str.len() + copied_text.len() + 3); // 3 for padding
 
-        let display_name = if account.name.len() > max_name_len {
-            format!("{}...", &account.name[..max_name_len.saturating_sub(3)])
+        let badges = format!(
+            "{}{}",
+            if marked { "✓ " } else { "" },
+            if account.favorite { "★ " } else { "" }
+        );
+        let prefix = if display.show_issuer {
+            issuer_prefix(account)
         } else {
-            account.name.clone()
+            String::new()
         };
+        let label = label_with_note(
+            &format!("{}{}{}", badges, prefix, account.name),
+            account.note.as_deref(),
+            max_name_len,
+        );
+        let display_name = truncate_to_width(&label, max_name_len);
 
         let spacing = " ".repeat(
             (max_width as usize)
                 .saturating_sub(1) // left padding
-                .saturating_sub(display_name.len())
+                .saturating_sub(display_name.width())
                 .saturating_sub(code_str.len())
                 .saturating_sub(copied_text.len()) // Always reserve space for " Copied!"
                 .saturating_sub(1), // right padding
@@ -258,6 +471,7 @@ impl ScreenBuffer {
             display_name, spacing, code_str, copied_indicator
         );
 
+        let line_len = line.len();
         if selected {
             if copied_indicator.is_empty() {
                 self.write_highlighted_line(row, line);
@@ -269,27 +483,307 @@ impl ScreenBuffer {
         } else {
             self.write_line(row, line);
         }
+        // Skip the single space of left padding and the badges
+        let prefix_start = 1 + badges.len();
+        if !prefix.is_empty() {
+            self.dim_range(row, prefix_start..prefix_start + prefix.len());
+        }
+        // Only the part of the name left visible after truncation is marked
+        let visible_end = 1 + if display_name == label {
+            label.len()
+        } else {
+            display_name.len().saturating_sub(3)
+        };
+        let name_start = prefix_start + prefix.len();
+        for (index, (offset, c)) in account.name.char_indices().enumerate() {
+            let start = name_start + offset;
+            let end = start + c.len_utf8();
+            if end <= visible_end && matched.contains(&index) {
+                self.underline_range(row, start..end);
+            }
+        }
+        if next_code_len > 0 {
+            // The code is followed by the copied indicator and a space of padding
+            let code_end = line_len - copied_indicator.len() - 1;
+            self.dim_range(row, code_end - next_code_len..code_end);
+        }
         Ok(())
     }
 }
 
+const DEFAULT_PERIOD: u32 = 30;
+const PROGRESS_BAR_WIDTH: usize = 30;
+
+/// Progress through the current time step, e.g. `|███▌      | 21s`.
+fn progress_line(period: u32, epoch: u64, now: Duration) -> String {
+    let period_ms = u128::from(period) * 1000;
+    let elapsed_ms = now
+        .as_millis()
+        .saturating_sub(u128::from(epoch) * 1000)
+        .checked_rem(period_ms)
+        .unwrap_or(0);
+    let secs_remaining = if period == 0 {
+        0
+    } else {
+        (period_ms - elapsed_ms).div_ceil(1000)
+    };
+
+    let bar_width = PROGRESS_BAR_WIDTH;
+    let filled_exact = if period == 0 {
+        0.0
+    } else {
+        elapsed_ms as f64 / period_ms as f64 * bar_width as f64
+    };
+    let filled_full = filled_exact.floor() as usize;
+    let partial = filled_exact - filled_full as f64;
+
+    let blocks = ["", "▏", "▎", "▍", "▌", "▋", "▊", "▉", "█"];
+    let partial_block = if filled_full < bar_width {
+        blocks[(partial * 8.0) as usize]
+    } else {
+        ""
+    };
+
+    let remaining = if filled_full < bar_width {
+        bar_width - filled_full - if partial_block.is_empty() { 0 } else { 1 }
+    } else {
+        0
+    };
+
+    format!(
+        "|{}{}{}| {:2}s",
+        "█".repeat(filled_full),
+        partial_block,
+        " ".repeat(remaining),
+        secs_remaining
+    )
+}
+
+/// Display toggles the user can flip while the dashboard is open.
+struct DisplayOptions {
+    show_issuer: bool,
+    /// Show the code for the next time step beside the current one
+    show_next: bool,
+    /// Print the secret-bearing otpauth URI under exported QR codes
+    show_uri: bool,
+    /// Show codes split in two groups, such as `123 456`
+    group_codes: bool,
+    theme: Theme,
+    /// A secret shown on the status line after pressing Shift-R
+    revealed: Option<RevealedSecret>,
+}
+
+impl Default for DisplayOptions {
+    fn default() -> Self {
+        Self {
+            show_issuer: true,
+            show_next: false,
+            show_uri: false,
+            group_codes: false,
+            theme: Theme::default(),
+            revealed: None,
+        }
+    }
+}
+
+// How long a revealed secret stays on screen
+const REVEAL_DURATION: Duration = Duration::from_secs(10);
+
+struct RevealedSecret {
+    name: String,
+    secret: Zeroizing<String>,
+    shown_at: Instant,
+}
+
+impl RevealedSecret {
+    fn is_expired(&self, now: Instant) -> bool {
+        now.duration_since(self.shown_at) >= REVEAL_DURATION
+    }
+
+    fn status_line(&self, now: Instant) -> String {
+        let left = REVEAL_DURATION.saturating_sub(now.duration_since(self.shown_at));
+        format!(
+            " Secret for {}: {} (hidden in {}s)",
+            self.name,
+            self.secret.as_str(),
+            left.as_secs_f64().ceil() as u64
+        )
+    }
+}
+
+/// "Issuer · " for accounts with a meaningful issuer, otherwise empty.
+fn issuer_prefix(account: &Account) -> String {
+    if account.issuer.is_empty() || account.issuer == DEFAULT_ISSUER {
+        String::new()
+    } else {
+        format!("{} · ", account.issuer)
+    }
+}
+
+// The header, a blank line, the progress bar and another blank line sit
+// above the account list, and the status line below it
+fn rows_below_header(term_height: u16) -> usize {
+    usize::from(term_height.saturating_sub(5))
+}
+
+/// How many accounts are listed out of `total`, and how many didn't fit in
+/// the `rows` on screen, e.g. " 3/57 accounts — ↑↓ select, Enter copy".
+fn status_line(shown: usize, total: usize, rows: usize) -> String {
+    let noun = if total == 1 { "account" } else { "accounts" };
+    let mut status = if shown == total {
+        format!(" {} {}", total, noun)
+    } else {
+        format!(" {}/{} {}", shown, total, noun)
+    };
+    if shown > rows {
+        status.push_str(&format!(", {} more below", shown - rows));
+    }
+    status.push_str(" — ↑↓ select, Enter copy");
+    status
+}
+
+fn select_next(selected: &mut usize, account_count: usize, term_height: u16) {
+    let max_items = account_count.min(rows_below_header(term_height));
+    if *selected + 1 < max_items {
+        *selected += 1;
+    }
+}
+
+// Smallest space worth showing a note in, including " (...)"
+const MIN_NOTE_WIDTH: usize = 10;
+
+/// `name` followed by as much of `note` as fits in `max_width`.
+fn label_with_note(name: &str, note: Option<&str>, max_width: usize) -> String {
+    match note {
+        Some(note) if !note.is_empty() && name.width() + MIN_NOTE_WIDTH <= max_width => {
+            // Leave room for " (" and ")"
+            let room = max_width - name.width() - 3;
+            format!("{} ({})", name, truncate_to_width(note, room))
+        }
+        _ => name.to_string(),
+    }
+}
+
+/// Shortens `name` to fit in `max_width` terminal columns, marking the cut
+/// with "...". Cuts on character boundaries so multibyte names are safe.
+fn truncate_to_width(name: &str, max_width: usize) -> String {
+    if name.width() <= max_width {
+        return name.to_string();
+    }
+
+    let budget = max_width.saturating_sub(3);
+    let mut width = 0;
+    let mut end = 0;
+    for (idx, c) in name.char_indices() {
+        width += c.width().unwrap_or(0);
+        if width > budget {
+            break;
+        }
+        end = idx + c.len_utf8();
+    }
+    format!("{}...", &name[..end])
+}
+
 // Define the dashboard modes
 enum DashboardMode {
     List,
     Search(String),
     Add,
     AddMethod,
+    /// Full-screen list of keybindings, left by pressing any key
+    Help,
+}
+
+/// Decoded secrets and generated codes keyed by account name, so a code is
+/// only computed when its time step changes rather than on every frame.
+/// Cleared whenever storage is reloaded, as an edit may change a secret.
+#[derive(Default)]
+struct CodeCache {
+    accounts: HashMap<String, CachedAccount>,
+}
+
+struct CachedAccount {
+    key: Zeroizing<Vec<u8>>,
+    /// Codes for the steps around the last one requested
+    codes: Vec<TotpCode>,
+}
+
+impl CodeCache {
+    fn generate(&mut self, account: &Account, duration: Duration) -> Result<TotpCode, AppError> {
+        let cached = match self.accounts.entry(account.name.clone()) {
+            Entry::Occupied(entry) => entry.into_mut(),
+            Entry::Vacant(entry) => entry.insert(CachedAccount {
+                key: decode_secret(&account.secret, account.encoding)?,
+                codes: Vec::new(),
+            }),
+        };
+
+        let step = account.time_step(duration);
+        if let Some(code) = cached.codes.iter().find(|code| code.step == step) {
+            return Ok(code.clone());
+        }
+        let code = generate_totp_with_key(account, &cached.key, duration)?;
+        // Keep the neighbouring steps, which the next code column still shows
+        cached.codes.retain(|code| code.step.abs_diff(step) <= 1);
+        cached.codes.push(code.clone());
+        Ok(code)
+    }
+
+    fn clear(&mut self) {
+        self.accounts.clear();
+    }
 }
 
 // Track recently copied accounts
 struct CopiedState {
     accounts: HashMap<String, SystemTime>,
+    /// How long a copied code stays on the clipboard, if it should be cleared
+    clear_after: Option<Duration>,
+    /// The last code copied and when, until it has been cleared
+    pending_clear: Option<(String, Instant)>,
 }
 
 impl CopiedState {
+    #[cfg(test)]
     fn new() -> Self {
+        Self::with_clear_after(None)
+    }
+
+    fn with_clear_after(clear_after: Option<Duration>) -> Self {
         Self {
             accounts: HashMap::new(),
+            clear_after,
+            pending_clear: None,
+        }
+    }
+
+    fn remember_copied_value(&mut self, value: String) {
+        if self.clear_after.is_some() {
+            self.pending_clear = Some((value, Instant::now()));
+        }
+    }
+
+    /// Returns the copied value once it has been on the clipboard long enough
+    /// to be cleared.
+    fn take_expired_value(&mut self, now: Instant) -> Option<String> {
+        let clear_after = self.clear_after?;
+        match &self.pending_clear {
+            Some((_, copied_at)) if now.duration_since(*copied_at) >= clear_after => {
+                self.pending_clear.take().map(|(value, _)| value)
+            }
+            _ => None,
+        }
+    }
+
+    /// Clears the clipboard if it still holds an expired code, leaving anything
+    /// the user has copied since alone.
+    fn clear_expired_clipboard(&mut self) {
+        if let Some(value) = self.take_expired_value(Instant::now()) {
+            if let Ok(mut clipboard) = Clipboard::new() {
+                if clipboard.get_text().is_ok_and(|text| text == value) {
+                    let _ = clipboard.clear();
+                }
+            }
         }
     }
 
@@ -323,36 +817,68 @@ fn get_filtered_accounts<'a>(
     matcher: &SkimMatcherV2,
 ) -> Vec<&'a crate::totp::Account> {
     match mode {
-        DashboardMode::List => storage.accounts.iter().collect(),
-        DashboardMode::Search(query) => {
-            let mut matches: Vec<_> = storage
-                .accounts
-                .iter()
-                .filter_map(|account| {
-                    matcher
-                        .fuzzy_match(&account.name, query)
-                        .map(|score| (score, account))
-                })
-                .collect();
-            matches.sort_by_key(|(score, _)| -score);
-            matches.into_iter().map(|(_, acc)| acc).collect()
-        }
-        DashboardMode::Add | DashboardMode::AddMethod => storage.accounts.iter().collect(),
+        DashboardMode::Search(query) => rank_accounts(&storage.accounts, query, matcher)
+            .into_iter()
+            .map(|(_, acc)| acc)
+            .collect(),
+        _ => {
+            // Storage is sorted by name, and the stable sort keeps that order
+            // within the pinned and unpinned accounts
+            let mut accounts: Vec<_> = storage.accounts.iter().collect();
+            accounts.sort_by_key(|account| !account.favorite);
+            accounts
+        }
     }
 }
 
-pub fn show(file_path: Option<&str>) -> Result<(), AppError> {
+pub fn show(
+    backend: &dyn StorageBackend,
+    theme: Theme,
+    clipboard_clear_after: Option<Duration>,
+    refresh: Duration,
+    show_uri: bool,
+    group_codes: bool,
+) -> Result<(), AppError> {
+    // Raw mode and the screen size are meaningless when piped, and reading
+    // keys needs a terminal too
+    if !io::stdout().is_terminal() || !io::stdin().is_terminal() {
+        return Err(AppError::InvalidInput(
+            "The dashboard needs a terminal; run 'hotpot code --all' to print every code instead"
+                .to_string(),
+        ));
+    }
+
+    // Loaded once here, then again whenever an action changes the accounts
+    let mut storage = get_storage(backend)?;
+    // Nothing is drawn until the passphrase, if one is set, has been given.
+    // Touch ID can stand in for it where available
+    if let Some(hash) = &storage.passphrase_hash {
+        if !crate::biometrics::authenticate("unlock your one-time codes") {
+            let input = Zeroizing::new(prompt_password("Passphrase: ")?);
+            if !crate::passphrase::verify(&input, hash)? {
+                return Err(AppError::InvalidInput("Incorrect passphrase".to_string()));
+            }
+        }
+    }
+
     let mut stdout = io::stdout();
     enable_raw_mode()?;
     queue!(stdout, Clear(ClearType::All), Hide)?;
 
     let mut mode = DashboardMode::List;
     let mut selected = 0;
+    // Names of the accounts marked for bulk deletion
+    let mut marked = HashSet::new();
     let matcher = SkimMatcherV2::default();
     let mut name_buffer = String::with_capacity(64);
-    let mut copied_state = CopiedState::new();
-    // Get storage at the start of each loop iteration
-    let mut storage = get_storage(file_path)?;
+    let mut copied_state = CopiedState::with_clear_after(clipboard_clear_after);
+    let mut codes = CodeCache::default();
+    let mut display = DisplayOptions {
+        show_uri,
+        group_codes,
+        theme,
+        ..DisplayOptions::default()
+    };
 
     // Initialize screen buffer
     let (term_width, term_height) = size()?;
@@ -364,44 +890,99 @@ pub fn show(file_path: Option<&str>) -> Result<(), AppError> {
         if new_width != buffer.width || new_height != buffer.height {
             buffer = ScreenBuffer::new(new_width, new_height);
         }
-        let max_display = (buffer.height - 4) as usize;
+        let max_display = buffer.account_rows();
 
         // Clear buffer for new frame
         buffer.clear();
 
         // Clean up old copied entries
         copied_state.cleanup_old_entries();
+        copied_state.clear_expired_clipboard();
+        let now = Instant::now();
+        if display
+            .revealed
+            .as_ref()
+            .is_some_and(|revealed| revealed.is_expired(now))
+        {
+            display.revealed = None;
+        }
 
         let filtered_accounts = get_filtered_accounts(&storage, &mode, &matcher);
 
         // Render to buffer
         buffer.render_header(&mode, &name_buffer);
-        buffer.render_progress_bar();
+        if matches!(mode, DashboardMode::Help) {
+            buffer.render_help();
+        } else {
+            buffer.render_progress_bar(filtered_accounts.get(selected).copied());
+            match &display.revealed {
+                Some(revealed) => buffer.render_revealed_secret(revealed, now),
+                None => buffer.render_status(filtered_accounts.len(), storage.accounts.len()),
+            }
+        }
 
         // Render account list to buffer
-        for (idx, account) in filtered_accounts.iter().take(max_display).enumerate() {
+        let shown = if matches!(mode, DashboardMode::Help) {
+            0
+        } else {
+            max_display
+        };
+        for (idx, account) in filtered_accounts.iter().take(shown).enumerate() {
             let is_selected = idx == selected;
-            buffer.render_account_line(account, 4 + idx as u16, is_selected, &copied_state)?;
+            let matched = match &mode {
+                DashboardMode::Search(query) => matcher
+                    .fuzzy_indices(&account.name, query)
+                    .map(|(_, indices)| indices)
+                    .unwrap_or_default(),
+                _ => Vec::new(),
+            };
+            buffer.render_account_line(
+                account,
+                4 + idx as u16,
+                is_selected,
+                marked.contains(&account.name),
+                &copied_state,
+                &display,
+                &mut codes,
+                &matched,
+            )?;
         }
 
         // Flush buffer to screen
-        buffer.flush_to_screen(&mut stdout)?;
+        buffer.flush_to_screen(&mut stdout, &display.theme)?;
+
+        // Nothing moves on the help screen, so it can wait longer
+        let interval = if matches!(mode, DashboardMode::Help) {
+            refresh.max(IDLE_REFRESH)
+        } else {
+            refresh
+        };
+        let wait = next_frame_wait(
+            SystemTime::now()
+                .duration_since(UNIX_EPOCH)
+                .expect("Time went backwards"),
+            interval,
+        );
 
         // Process user input
         match handle_input(
+            wait,
             &mut mode,
             &mut selected,
+            &mut marked,
             &filtered_accounts,
             buffer.height,
             buffer.width,
             &mut stdout,
             &mut name_buffer,
             &mut copied_state,
-            file_path,
+            &mut display,
+            backend,
         )? {
             InputResult::Continue => {
                 // Continue the loop
             }
+            InputResult::Redraw => buffer.invalidate(),
             InputResult::Exit => {
                 queue!(stdout, Clear(ClearType::All), MoveTo(0, 0))?;
                 stdout.flush()?;
@@ -409,12 +990,17 @@ pub fn show(file_path: Option<&str>) -> Result<(), AppError> {
             }
             InputResult::RefreshStorage => {
                 // Storage will be refreshed at the start of the next loop
-                storage = get_storage(file_path)?;
+                storage = get_storage(backend)?;
+                marked.retain(|name| storage.accounts.iter().any(|a| &a.name == name));
+                codes.clear();
+                buffer.invalidate();
             }
             InputResult::RefreshStorageAndResetMode => {
                 // Storage will be refreshed and mode reset to List
-                storage = get_storage(file_path)?;
+                storage = get_storage(backend)?;
                 mode = DashboardMode::List;
+                codes.clear();
+                buffer.invalidate();
             }
         }
     }
@@ -424,8 +1010,22 @@ pub fn show(file_path: Option<&str>) -> Result<(), AppError> {
     Ok(())
 }
 
+// The slowest the dashboard redraws while nothing on it is moving
+const IDLE_REFRESH: Duration = Duration::from_secs(1);
+
+/// How long to wait for input before drawing the next frame. Frames land
+/// on multiples of `interval` since the epoch, so they stay evenly spaced
+/// however long drawing or a key press took, and an interval that divides
+/// a second ticks the countdown over exactly when its second changes.
+fn next_frame_wait(now: Duration, interval: Duration) -> Duration {
+    let interval = interval.as_millis().max(1);
+    Duration::from_millis((interval - now.as_millis() % interval) as u64)
+}
+
 enum InputResult {
     Continue,
+    /// Something was drawn outside the screen buffer, so repaint it all
+    Redraw,
     Exit,
     RefreshStorage,
     RefreshStorageAndResetMode,
@@ -433,18 +1033,28 @@ enum InputResult {
 
 #[allow(clippy::too_many_arguments)]
 fn handle_input(
+    wait: Duration,
     mode: &mut DashboardMode,
     selected: &mut usize,
+    marked: &mut HashSet<String>,
     accounts: &[&crate::totp::Account],
     term_height: u16,
     term_width: u16,
     stdout: &mut io::Stdout,
     name_buffer: &mut String,
     copied_state: &mut CopiedState,
-    file_path: Option<&str>,
+    display: &mut DisplayOptions,
+    backend: &dyn StorageBackend,
 ) -> Result<InputResult, AppError> {
-    if poll(std::time::Duration::from_millis(250))? {
-        match read()? {
+    if poll(wait)? {
+        let event = read()?;
+        if matches!(mode, DashboardMode::Help) {
+            if let Event::Key(_) = event {
+                *mode = DashboardMode::List;
+            }
+            return Ok(InputResult::Continue);
+        }
+        match event {
             Event::Key(KeyEvent {
                 code: KeyCode::Char('c'),
                 modifiers: KeyModifiers::CONTROL,
@@ -459,6 +1069,7 @@ fn handle_input(
             Event::Key(KeyEvent {
                 code: KeyCode::Esc, ..
             }) => match mode {
+                DashboardMode::List if !marked.is_empty() => marked.clear(),
                 DashboardMode::List => return Ok(InputResult::Exit),
                 _ => *mode = DashboardMode::List,
             },
@@ -470,10 +1081,12 @@ fn handle_input(
                     c,
                     mode,
                     selected,
+                    marked,
                     accounts,
                     stdout,
                     name_buffer,
-                    file_path,
+                    display,
+                    backend,
                 );
             }
             Event::Key(KeyEvent {
@@ -491,19 +1104,14 @@ fn handle_input(
             },
             Event::Key(KeyEvent {
                 code: KeyCode::Up, ..
-            }) => {
-                if *selected > 0 {
-                    *selected -= 1;
-                }
+            }) if *selected > 0 => {
+                *selected -= 1;
             }
             Event::Key(KeyEvent {
                 code: KeyCode::Down,
                 ..
             }) => {
-                let max_items = accounts.len().min(term_height as usize - 4);
-                if *selected + 1 < max_items {
-                    *selected += 1;
-                }
+                select_next(selected, accounts.len(), term_height);
             }
             Event::Key(KeyEvent {
                 code: KeyCode::Enter,
@@ -511,7 +1119,7 @@ fn handle_input(
             }) => match mode {
                 DashboardMode::Add => {
                     if !name_buffer.trim().is_empty() {
-                        return handle_add_mode(stdout, name_buffer, file_path);
+                        return handle_add_mode(stdout, name_buffer, backend);
                     }
                 }
                 _ => {
@@ -537,35 +1145,50 @@ fn handle_input(
     Ok(InputResult::Continue)
 }
 
+#[allow(clippy::too_many_arguments)]
 fn handle_char_input(
     c: char,
     mode: &mut DashboardMode,
     selected: &mut usize,
+    marked: &mut HashSet<String>,
     accounts: &[&crate::totp::Account],
     stdout: &mut io::Stdout,
     name_buffer: &mut String,
-    file_path: Option<&str>,
+    display: &mut DisplayOptions,
+    backend: &dyn StorageBackend,
 ) -> Result<InputResult, AppError> {
     match mode {
-        DashboardMode::List => {
-            handle_list_mode_char(c, mode, selected, accounts, stdout, file_path)
-        }
+        DashboardMode::List => handle_list_mode_char(
+            c, mode, selected, marked, accounts, stdout, display, backend,
+        ),
         DashboardMode::Search(query) => handle_search_mode_char(c, query, selected),
         DashboardMode::Add => handle_add_mode_char(c, name_buffer),
         DashboardMode::AddMethod => {
-            handle_add_method_mode_char(c, mode, stdout, name_buffer, file_path)
+            handle_add_method_mode_char(c, mode, stdout, name_buffer, backend)
         }
+        // Any key leaves help before reaching here
+        DashboardMode::Help => Ok(InputResult::Continue),
     }
 }
 
+#[allow(clippy::too_many_arguments)]
 fn handle_list_mode_char(
     c: char,
     mode: &mut DashboardMode,
     selected: &mut usize,
+    marked: &mut HashSet<String>,
     accounts: &[&crate::totp::Account],
     stdout: &mut io::Stdout,
-    file_path: Option<&str>,
+    display: &mut DisplayOptions,
+    backend: &dyn StorageBackend,
 ) -> Result<InputResult, AppError> {
+    // Only the shifted key, so a stray 'r' doesn't ask to show a secret
+    if c == 'R' {
+        return match accounts.get(*selected) {
+            Some(account) => handle_reveal_secret(account, stdout, display),
+            None => Ok(InputResult::Continue),
+        };
+    }
     match c.to_ascii_lowercase() {
         'f' => {
             *mode = DashboardMode::Search(String::new());
@@ -576,24 +1199,89 @@ fn handle_list_mode_char(
             *mode = DashboardMode::AddMethod;
             Ok(InputResult::Continue)
         }
+        'd' if !marked.is_empty() => {
+            let mut names: Vec<&str> = marked.iter().map(String::as_str).collect();
+            names.sort_unstable();
+            handle_delete_confirmation(&names, stdout, &display.theme, backend)
+        }
         'd' => {
             if let Some(account) = accounts.get(*selected) {
-                handle_delete_confirmation(account, stdout, file_path)
+                handle_delete_confirmation(&[&account.name], stdout, &display.theme, backend)
             } else {
                 Ok(InputResult::Continue)
             }
         }
         'e' => {
             if let Some(account) = accounts.get(*selected) {
-                handle_export_qr(account, stdout)
+                handle_export_qr(account, display.show_uri, stdout)
+            } else {
+                Ok(InputResult::Continue)
+            }
+        }
+        'm' => {
+            if let Some(account) = accounts.get(*selected) {
+                handle_edit_account(account, stdout, backend)
+            } else {
+                Ok(InputResult::Continue)
+            }
+        }
+        'i' => {
+            display.show_issuer = !display.show_issuer;
+            Ok(InputResult::Continue)
+        }
+        'n' => {
+            display.show_next = !display.show_next;
+            Ok(InputResult::Continue)
+        }
+        'p' => {
+            if let Some(account) = accounts.get(*selected) {
+                toggle_favorite(account, selected, accounts, backend)
             } else {
                 Ok(InputResult::Continue)
             }
         }
+        ' ' => {
+            if let Some(account) = accounts.get(*selected) {
+                toggle_mark(marked, &account.name);
+            }
+            Ok(InputResult::Continue)
+        }
+        '?' => {
+            *mode = DashboardMode::Help;
+            Ok(InputResult::Continue)
+        }
         _ => Ok(InputResult::Continue),
     }
 }
 
+fn toggle_mark(marked: &mut HashSet<String>, name: &str) {
+    if !marked.remove(name) {
+        marked.insert(name.to_string());
+    }
+}
+
+/// Pins or unpins `account`, keeping it selected as it moves in the list.
+fn toggle_favorite(
+    account: &Account,
+    selected: &mut usize,
+    accounts: &[&Account],
+    backend: &dyn StorageBackend,
+) -> Result<InputResult, AppError> {
+    let mut updated = account.clone();
+    updated.favorite = !account.favorite;
+    // Where the account lands once the list is re-sorted
+    let key = |favorite: bool, name: &str| (!favorite, name.to_string());
+    *selected = accounts
+        .iter()
+        .filter(|other| {
+            other.name != account.name
+                && key(other.favorite, &other.name) < key(updated.favorite, &updated.name)
+        })
+        .count();
+    update_account(updated, backend)?;
+    Ok(InputResult::RefreshStorage)
+}
+
 fn handle_search_mode_char(
     c: char,
     query: &mut String,
@@ -612,13 +1300,15 @@ fn handle_add_mode_char(c: char, name_buffer: &mut String) -> Result<InputResult
 fn handle_add_method_mode_char(
     c: char,
     mode: &mut DashboardMode,
-    #[cfg_attr(not(target_os = "macos"), allow(unused_variables))] stdout: &mut io::Stdout,
+    stdout: &mut io::Stdout,
     name_buffer: &mut String,
-    #[cfg_attr(not(target_os = "macos"), allow(unused_variables))] file_path: Option<&str>,
+    backend: &dyn StorageBackend,
 ) -> Result<InputResult, AppError> {
     match c.to_ascii_lowercase() {
-        #[cfg(target_os = "macos")]
-        's' => handle_screenshot_add(stdout, file_path),
+        #[cfg(any(target_os = "macos", target_os = "linux", windows))]
+        's' => handle_screenshot_add(stdout, backend),
+        'i' => handle_image_add(stdout, backend),
+        'p' => handle_paste_add(stdout, backend),
         'm' => {
             *mode = DashboardMode::Add;
             name_buffer.clear();
@@ -645,13 +1335,26 @@ fn restore_dashboard_state(stdout: &mut io::Stdout) -> Result<(), AppError> {
 fn handle_add_mode(
     stdout: &mut io::Stdout,
     name: &str,
-    file_path: Option<&str>,
+    backend: &dyn StorageBackend,
 ) -> Result<InputResult, AppError> {
     setup_terminal_for_input(stdout)?;
 
+    // The secret moves straight into the account, which wipes it on drop
     if let Ok(secret) = prompt_password("Enter the Base32 secret: ") {
-        if let Ok(()) = save_account(name, &secret, file_path) {
-            queue!(stdout, Print(format!("Added account: {}", name)))?;
+        let account = Account::new(name.to_string(), secret);
+        let result = match confirm_secret_length(&account) {
+            Ok(true) => save_account(account, false, backend),
+            Ok(false) => Err(AppError::InvalidInput("Secret is too short".to_string())),
+            Err(e) => Err(e),
+        };
+        match result {
+            Ok(_) => queue!(stdout, Print(format!("Added account: {}", name)))?,
+            Err(e) => {
+                println!("Failed to save account: {}", e);
+                println!("Press Enter to return to dashboard...");
+                let mut input = String::new();
+                io::stdin().read_line(&mut input)?;
+            }
         }
     }
 
@@ -660,21 +1363,40 @@ fn handle_add_mode(
     Ok(InputResult::RefreshStorageAndResetMode)
 }
 
+/// Asks once before deleting all the accounts in `names`.
 fn handle_delete_confirmation(
-    account: &crate::totp::Account,
+    names: &[&str],
     stdout: &mut io::Stdout,
-    file_path: Option<&str>,
+    theme: &Theme,
+    backend: &dyn StorageBackend,
 ) -> Result<InputResult, AppError> {
+    if confirm_on_header(&delete_prompt(names), stdout, theme)?
+        && delete_accounts(names, backend).is_ok()
+    {
+        Ok(InputResult::RefreshStorage)
+    } else {
+        Ok(InputResult::Redraw)
+    }
+}
+
+/// Asks a yes/no question on the header line, defaulting to no.
+fn confirm_on_header(
+    prompt: &str,
+    stdout: &mut io::Stdout,
+    theme: &Theme,
+) -> Result<bool, AppError> {
     // Clear only the first line and show cursor
-    queue!(
-        stdout,
-        MoveTo(0, 0),
-        Clear(ClearType::CurrentLine),
-        Show,
-        SetForegroundColor(Color::Red),
-        Print(format!("Delete account '{}'? [y/N] ", account.name)),
-        SetForegroundColor(Color::Reset)
-    )?;
+    queue!(stdout, MoveTo(0, 0), Clear(ClearType::CurrentLine), Show)?;
+    if theme.styled {
+        queue!(
+            stdout,
+            SetForegroundColor(Color::Red),
+            Print(prompt),
+            SetForegroundColor(Color::Reset)
+        )?;
+    } else {
+        queue!(stdout, Print(prompt))?;
+    }
     stdout.flush()?;
     disable_raw_mode()?;
 
@@ -682,29 +1404,128 @@ fn handle_delete_confirmation(
     let mut confirm = String::new();
     io::stdin().read_line(&mut confirm)?;
 
-    let result = if confirm.trim().eq_ignore_ascii_case("y") {
-        if let Ok(()) = delete_account(&account.name, file_path) {
-            // Clear confirmation message
-            queue!(stdout, MoveTo(0, 0), Clear(ClearType::CurrentLine))?;
-            stdout.flush()?;
-            InputResult::RefreshStorage
-        } else {
-            InputResult::Continue
-        }
-    } else {
-        InputResult::Continue
-    };
-
     enable_raw_mode()?;
-    queue!(stdout, Hide)?;
+    // Clear confirmation message
+    queue!(stdout, MoveTo(0, 0), Clear(ClearType::CurrentLine), Hide)?;
     stdout.flush()?;
 
-    Ok(result)
+    Ok(confirm.trim().eq_ignore_ascii_case("y"))
 }
 
-fn copy_code_to_clipboard(
+/// Shows the account's secret on the status line for a few seconds, if
+/// confirmed. It is never written anywhere else.
+fn handle_reveal_secret(
+    account: &Account,
+    stdout: &mut io::Stdout,
+    display: &mut DisplayOptions,
+) -> Result<InputResult, AppError> {
+    let prompt = format!("Show the secret for '{}'? [y/N] ", account.name);
+    if confirm_on_header(&prompt, stdout, &display.theme)? {
+        display.revealed = Some(RevealedSecret {
+            name: account.name.clone(),
+            secret: account.base32_secret(),
+            shown_at: Instant::now(),
+        });
+    }
+    Ok(InputResult::Redraw)
+}
+
+fn delete_prompt(names: &[&str]) -> String {
+    match names {
+        [name] => format!("Delete account '{}'? [y/N] ", name),
+        _ => format!("Delete {} accounts? [y/N] ", names.len()),
+    }
+}
+
+fn handle_edit_account(
     account: &crate::totp::Account,
-    _selected_idx: usize,
+    stdout: &mut io::Stdout,
+    backend: &dyn StorageBackend,
+) -> Result<InputResult, AppError> {
+    setup_terminal_for_input(stdout)?;
+
+    println!(
+        "Editing {} (press Enter to keep the current value)\n",
+        account.name
+    );
+    let issuer = prompt_field("Issuer", &account.issuer)?;
+    let algorithm = prompt_field("Algorithm (SHA1/SHA256/SHA512/STEAM)", &account.algorithm)?;
+    let digits = prompt_field("Digits", &account.digits.to_string())?;
+    let period = prompt_field("Period", &account.period.to_string())?;
+
+    let mut updated = (*account).clone();
+    let result = apply_account_edits(&mut updated, &issuer, &algorithm, &digits, &period)
+        .and_then(|_| update_account(updated, backend));
+    if let Err(e) = result {
+        println!("\nFailed to update account: {}", e);
+        println!("Press Enter to return to dashboard...");
+        let mut input = String::new();
+        io::stdin().read_line(&mut input)?;
+    }
+
+    restore_dashboard_state(stdout)?;
+
+    Ok(InputResult::RefreshStorage)
+}
+
+fn prompt_field(label: &str, current: &str) -> Result<String, AppError> {
+    print!("{} [{}]: ", label, current);
+    io::stdout().flush()?;
+    let mut input = String::new();
+    io::stdin().read_line(&mut input)?;
+    Ok(input.trim().to_string())
+}
+
+/// Applies the edit prompt's answers to `account`, leaving fields whose
+/// answer is empty unchanged.
+fn apply_account_edits(
+    account: &mut Account,
+    issuer: &str,
+    algorithm: &str,
+    digits: &str,
+    period: &str,
+) -> Result<(), AppError> {
+    if !issuer.is_empty() {
+        account.issuer = issuer.to_string();
+    }
+    if !algorithm.is_empty() {
+        match normalize_algorithm(algorithm) {
+            Some(algorithm) => account.algorithm = algorithm.to_string(),
+            None => {
+                return Err(AppError::InvalidInput(format!(
+                    "Unsupported algorithm: {}",
+                    algorithm
+                )));
+            }
+        }
+    }
+    if !digits.is_empty() {
+        account.digits = match digits.parse() {
+            Ok(digits @ MIN_DIGITS..=MAX_DIGITS) => digits,
+            _ => {
+                return Err(AppError::InvalidInput(format!(
+                    "Digits must be a number from {} to {}",
+                    MIN_DIGITS, MAX_DIGITS
+                )));
+            }
+        };
+    }
+    if !period.is_empty() {
+        account.period = match period.parse() {
+            Ok(period) if period > 0 => period,
+            _ => {
+                return Err(AppError::InvalidInput(
+                    "Period must be a positive number of seconds".to_string(),
+                ));
+            }
+        };
+    }
+    Ok(())
+}
+
+fn copy_code_to_clipboard(
+    account: &crate::totp::Account,
+    _selected_idx: usize,
     _term_width: u16,
     _stdout: &mut io::Stdout,
     copied_state: &mut CopiedState,
@@ -712,9 +1533,12 @@ fn copy_code_to_clipboard(
     let duration = SystemTime::now()
         .duration_since(UNIX_EPOCH)
         .expect("Time went backwards");
-    if let Ok(code) = generate_totp(account, duration) {
+    if let Ok(totp) = generate_totp_full(account, duration) {
         if let Ok(mut clipboard) = Clipboard::new() {
-            let _ = clipboard.set_text(format!("{}", code));
+            let value = totp.formatted;
+            if clipboard.set_text(value.clone()).is_ok() {
+                copied_state.remember_copied_value(value);
+            }
             copied_state.mark_copied(&account.name);
         }
     }
@@ -723,6 +1547,7 @@ fn copy_code_to_clipboard(
 
 fn handle_export_qr(
     account: &crate::totp::Account,
+    show_uri: bool,
     stdout: &mut io::Stdout,
 ) -> Result<InputResult, AppError> {
     use qrcode::{QrCode, render::unicode};
@@ -731,8 +1556,12 @@ fn handle_export_qr(
 
     // Generate the otpauth URI
     let uri = account.generate_uri();
-    println!("QR Code for {}", account.name);
-    println!("\nGenerated URI: {}\n", uri);
+    println!("QR Code for {}\n", account.name);
+    // The URI holds the secret in plain text, so it stays out of scrollback
+    // unless asked for
+    if show_uri {
+        println!("Generated URI: {}\n", uri);
+    }
 
     // Generate and display QR code
     let code =
@@ -751,90 +1580,44 @@ fn handle_export_qr(
 
     restore_dashboard_state(stdout)?;
 
-    Ok(InputResult::Continue)
+    Ok(InputResult::Redraw)
 }
 
-#[cfg(target_os = "macos")]
+#[cfg(any(target_os = "macos", target_os = "linux", windows))]
 fn handle_screenshot_add(
     stdout: &mut io::Stdout,
-    file_path: Option<&str>,
+    backend: &dyn StorageBackend,
 ) -> Result<InputResult, AppError> {
-    use std::fs;
-    use std::process::Command;
-
     setup_terminal_for_input(stdout)?;
 
     println!("Screenshot mode - select area to capture QR code");
     println!("Position your cursor and drag to select the QR code area...");
 
-    // Create temporary file path
-    let temp_path = "/tmp/hotpot_screenshot.png";
+    // A unique file, as a fixed name in the shared temp directory could be
+    // planted or read by other users. It's deleted when dropped
+    let temp_file = tempfile::Builder::new()
+        .prefix("hotpot_screenshot")
+        .suffix(".png")
+        .tempfile()?;
+    let temp_path = temp_file.path().to_string_lossy();
 
-    // Call screencapture with interactive selection
-    let output = Command::new("screencapture")
-        .arg("-i") // Interactive selection
-        .arg("-r") // No drop shadow
-        .arg(temp_path)
-        .output()
-        .map_err(|e| AppError::new(format!("Failed to call screencapture: {}", e)))?;
-
-    if !output.status.success() {
+    let captured = capture_screenshot(&temp_path).unwrap_or_else(|e| {
+        println!("{}", e);
+        false
+    });
+    if !captured {
         println!("Screenshot cancelled or failed");
         println!("Press Enter to return to dashboard...");
         let mut input = String::new();
         io::stdin().read_line(&mut input)?;
 
         restore_dashboard_state(stdout)?;
-        return Ok(InputResult::Continue);
-    }
-
-    // Read and decode QR code from screenshot
-    match decode_qr_from_image(temp_path) {
-        Ok(qr_data) => {
-            // Clean up temp file
-            let _ = fs::remove_file(temp_path);
-
-            // Try to parse as otpauth URI
-            if let Some(extracted_name) = extract_account_from_otpauth(&qr_data) {
-                if let Some(secret) = extract_secret_from_otpauth(&qr_data) {
-                    // Prompt for account name with default
-                    println!(
-                        "Enter account name (press Enter for default) [{}]: ",
-                        extracted_name
-                    );
-                    let mut input = String::new();
-                    io::stdin().read_line(&mut input)?;
-
-                    let account_name = input.trim();
-                    let final_name = if account_name.is_empty() {
-                        extracted_name
-                    } else {
-                        account_name.to_string()
-                    };
-
-                    match save_account(&final_name, &secret, file_path) {
-                        Ok(()) => {
-                            println!("Successfully added account: {}", final_name);
-                        }
-                        Err(e) => {
-                            println!("Failed to save account: {}", e);
-                        }
-                    }
-                } else {
-                    println!("Could not extract secret from QR code");
-                }
-            } else {
-                println!("QR code does not appear to contain a valid TOTP setup");
-                println!("QR code contents: {}", qr_data);
-            }
-        }
-        Err(e) => {
-            // Clean up temp file
-            let _ = fs::remove_file(temp_path);
-            println!("Failed to decode QR code: {}", e);
-        }
+        return Ok(InputResult::Redraw);
     }
 
+    // Read and decode QR codes from screenshot
+    add_accounts_from_image(&temp_path, backend)?;
+
     println!("Press Enter to return to dashboard...");
     let mut input = String::new();
     io::stdin().read_line(&mut input)?;
@@ -844,81 +1627,279 @@ fn handle_screenshot_add(
     Ok(InputResult::RefreshStorageAndResetMode)
 }
 
-#[cfg(target_os = "macos")]
-fn decode_qr_from_image(image_path: &str) -> Result<String, AppError> {
-    use image::ImageReader;
-    use rqrr::PreparedImage;
+fn handle_image_add(
+    stdout: &mut io::Stdout,
+    backend: &dyn StorageBackend,
+) -> Result<InputResult, AppError> {
+    setup_terminal_for_input(stdout)?;
+
+    println!("Path to an image with a QR code (leave empty to cancel): ");
+    let mut input = String::new();
+    io::stdin().read_line(&mut input)?;
+    let path = image_path(&input);
+
+    if !path.is_empty() {
+        add_accounts_from_image(path, backend)?;
+    }
+
+    println!("Press Enter to return to dashboard...");
+    let mut input = String::new();
+    io::stdin().read_line(&mut input)?;
 
-    // Load the image
-    let img = ImageReader::open(image_path)
-        .map_err(|e| AppError::new(format!("Failed to open image: {}", e)))?
-        .decode()
-        .map_err(|e| AppError::new(format!("Failed to decode image: {}", e)))?;
+    restore_dashboard_state(stdout)?;
 
-    // Convert to luma (grayscale) for QR detection
-    let luma_img = img.to_luma8();
+    Ok(InputResult::RefreshStorageAndResetMode)
+}
 
-    // Prepare image for QR detection
-    let mut prepared = PreparedImage::prepare(luma_img);
+/// Tidies up a typed image path. Terminals quote paths that are dragged
+/// into them when they contain spaces.
+fn image_path(input: &str) -> &str {
+    let path = input.trim();
+    ['\'', '"']
+        .iter()
+        .find_map(|quote| path.strip_prefix(*quote)?.strip_suffix(*quote))
+        .unwrap_or(path)
+}
 
-    // Try to find and decode QR codes
-    let grids = prepared.detect_grids();
-    if grids.is_empty() {
-        return Err(AppError::new("No QR code found in image".to_string()));
-    }
+fn handle_paste_add(
+    stdout: &mut io::Stdout,
+    backend: &dyn StorageBackend,
+) -> Result<InputResult, AppError> {
+    setup_terminal_for_input(stdout)?;
 
-    // Decode the first QR code found
-    let (_, content) = grids[0]
-        .decode()
-        .map_err(|e| AppError::new(format!("Failed to decode QR code: {:?}", e)))?;
+    add_accounts_from_qr_codes(crate::qr::load_qr_codes_from_clipboard(), backend)?;
 
-    Ok(content)
+    println!("Press Enter to return to dashboard...");
+    let mut input = String::new();
+    io::stdin().read_line(&mut input)?;
+
+    restore_dashboard_state(stdout)?;
+
+    Ok(InputResult::RefreshStorageAndResetMode)
 }
 
-#[cfg(target_os = "macos")]
-fn extract_account_from_otpauth(uri: &str) -> Option<String> {
-    if !uri.starts_with("otpauth://totp/") {
-        return None;
+/// Decodes every QR code in the image at `path` and offers to add each one.
+fn add_accounts_from_image(path: &str, backend: &dyn StorageBackend) -> Result<(), AppError> {
+    add_accounts_from_qr_codes(crate::qr::load_qr_codes_from_image(path), backend)
+}
+
+/// Offers to add each of the QR codes decoded from an image.
+fn add_accounts_from_qr_codes(
+    codes: Result<Vec<Result<String, AppError>>, AppError>,
+    backend: &dyn StorageBackend,
+) -> Result<(), AppError> {
+    match codes {
+        Ok(codes) => {
+            let total = codes.len();
+            for (index, code) in codes.into_iter().enumerate() {
+                if total > 1 {
+                    println!("QR code {} of {}:", index + 1, total);
+                }
+                match code {
+                    Ok(qr_data) => add_account_from_qr(&qr_data, backend)?,
+                    Err(e) => println!("{}", e),
+                }
+            }
+        }
+        Err(e) => println!("{}", e),
     }
+    Ok(())
+}
+
+fn add_account_from_qr(qr_data: &str, backend: &dyn StorageBackend) -> Result<(), AppError> {
+    warn_issuer_mismatch(qr_data);
+    let account = match Account::from_otpauth_uri(qr_data) {
+        Ok(account) => account,
+        Err(e) => {
+            println!("QR code does not contain a usable TOTP setup: {}", e);
+            println!("QR code contents: {}", qr_data);
+            return Ok(());
+        }
+    };
 
-    // Extract account name from URI path
-    let path_start = uri.find("otpauth://totp/")?;
-    let path = &uri[path_start + 15..]; // Skip "otpauth://totp/"
+    // Prompt for account name with default
+    println!(
+        "Enter account name (press Enter for default) [{}]: ",
+        account.name
+    );
+    let mut input = String::new();
+    io::stdin().read_line(&mut input)?;
 
-    if let Some(query_start) = path.find('?') {
-        let account_part = &path[..query_start];
-        // URL decode and extract just the account name
-        Some(urlencoding::decode(account_part).ok()?.to_string())
+    let account_name = input.trim();
+    let account = if account_name.is_empty() {
+        account
     } else {
-        Some(urlencoding::decode(path).ok()?.to_string())
+        Account {
+            name: account_name.to_string(),
+            ..account
+        }
+    };
+
+    let final_name = account.name.trim().to_string();
+    let result = match confirm_secret_length(&account) {
+        Ok(true) => save_account(account, false, backend),
+        Ok(false) => Err(AppError::InvalidInput("Secret is too short".to_string())),
+        Err(e) => Err(e),
+    };
+    match result {
+        Ok(_) => {
+            println!("Successfully added account: {}", final_name);
+        }
+        Err(e) => {
+            println!("Failed to save account: {}", e);
+        }
     }
+    Ok(())
 }
 
+/// Lets the user select a screen region and saves it to `path`. Returns
+/// false if the capture was cancelled.
 #[cfg(target_os = "macos")]
-fn extract_secret_from_otpauth(uri: &str) -> Option<String> {
-    use url::Url;
+fn capture_screenshot(path: &str) -> Result<bool, AppError> {
+    use std::process::Command;
+
+    // Call screencapture with interactive selection
+    let output = Command::new("screencapture")
+        .arg("-i") // Interactive selection
+        .arg("-r") // No drop shadow
+        .arg(path)
+        .output()
+        .map_err(|e| AppError::new(format!("Failed to call screencapture: {}", e)))?;
+    Ok(output.status.success())
+}
 
-    let parsed = Url::parse(uri).ok()?;
-    let pairs: std::collections::HashMap<_, _> = parsed.query_pairs().collect();
-    pairs.get("secret").map(|s| s.to_string())
+/// Lets the user select a screen region and saves it to `path`, using
+/// grim and slurp on Wayland or maim or scrot on X11. Returns false if the
+/// capture was cancelled.
+#[cfg(target_os = "linux")]
+fn capture_screenshot(path: &str) -> Result<bool, AppError> {
+    use std::io::ErrorKind;
+    use std::process::Command;
+
+    // Runs a capture tool, returning None if it isn't installed
+    let run = |program: &str, args: &[&str]| -> Result<Option<bool>, AppError> {
+        match Command::new(program).args(args).output() {
+            Ok(output) => Ok(Some(output.status.success())),
+            Err(e) if e.kind() == ErrorKind::NotFound => Ok(None),
+            Err(e) => Err(AppError::new(format!("Failed to call {}: {}", program, e))),
+        }
+    };
+
+    if std::env::var_os("WAYLAND_DISPLAY").is_some() {
+        // grim captures the region that slurp lets the user draw
+        match Command::new("slurp").output() {
+            Ok(output) if !output.status.success() => return Ok(false),
+            Ok(output) => {
+                let region = String::from_utf8_lossy(&output.stdout).trim().to_string();
+                if let Some(captured) = run("grim", &["-g", &region, path])? {
+                    return Ok(captured);
+                }
+            }
+            Err(e) if e.kind() == ErrorKind::NotFound => {}
+            Err(e) => return Err(AppError::new(format!("Failed to call slurp: {}", e))),
+        }
+    }
+
+    for (program, args) in [("maim", ["-s", path]), ("scrot", ["-s", path])] {
+        if let Some(captured) = run(program, &args)? {
+            return Ok(captured);
+        }
+    }
+
+    Err(AppError::new(
+        "No screenshot tool found. Install grim and slurp (Wayland), or maim or scrot (X11)",
+    ))
+}
+
+/// Lets the user snip a screen region with Snipping Tool, which puts it on
+/// the clipboard, and saves it to `path`. Returns false if nothing was
+/// snipped in time.
+#[cfg(windows)]
+fn capture_screenshot(path: &str) -> Result<bool, AppError> {
+    use std::process::Command;
+    use std::thread;
+
+    // How long the user has to draw the region
+    const SNIP_TIMEOUT: Duration = Duration::from_secs(60);
+
+    let mut clipboard = Clipboard::new()
+        .map_err(|e| AppError::new(format!("Failed to open the clipboard: {}", e)))?;
+    // An image already on the clipboard would be mistaken for the snip, so
+    // whatever is there is set aside and put back afterwards
+    let previous_text = clipboard.get_text().ok();
+    let previous_image = match previous_text {
+        Some(_) => None,
+        None => clipboard.get_image().ok(),
+    };
+    let _ = clipboard.clear();
+    let restore = |clipboard: &mut Clipboard| {
+        let _ = clipboard.clear();
+        if let Some(text) = previous_text {
+            let _ = clipboard.set_text(text);
+        } else if let Some(image) = previous_image {
+            let _ = clipboard.set_image(image);
+        }
+    };
+
+    // Older versions of Windows 10 only open the region picker through the
+    // screen clip URI
+    let launched = Command::new("SnippingTool").arg("/clip").spawn().is_ok()
+        || Command::new("explorer")
+            .arg("ms-screenclip:")
+            .spawn()
+            .is_ok();
+    if !launched {
+        restore(&mut clipboard);
+        return Err(AppError::new(
+            "No screenshot tool found. Screen capture needs Snipping Tool or Snip & Sketch",
+        ));
+    }
+    println!(
+        "Waiting up to {} seconds for the snip...",
+        SNIP_TIMEOUT.as_secs()
+    );
+
+    let started = Instant::now();
+    let mut snip = None;
+    while started.elapsed() < SNIP_TIMEOUT {
+        if let Ok(image) = clipboard.get_image() {
+            snip = Some(image);
+            break;
+        }
+        thread::sleep(Duration::from_millis(250));
+    }
+    // This also takes the snip, which may show a secret, off the clipboard
+    restore(&mut clipboard);
+
+    let Some(snip) = snip else {
+        return Ok(false);
+    };
+    let img = crate::qr::clipboard_image(snip)?;
+    img.save_with_format(path, image::ImageFormat::Png)
+        .map_err(|e| AppError::Storage(format!("Failed to write image {}: {}", path, e)))?;
+    Ok(true)
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
-    use crate::Storage;
-    use crate::totp::Account;
+    use crate::storage::{FileBackend, InMemoryBackend, Storage};
+    use crate::totp::{Account, SecretEncoding, generate_totp};
     use std::time::{Duration, SystemTime};
 
     fn create_test_account(name: &str) -> Account {
         Account {
             name: name.to_string(),
-            secret: "JBSWY3DPEHPK3PXP".to_string(),
+            secret: "JBSWY3DPEHPK3PXP".into(),
+            encoding: SecretEncoding::Base32,
             issuer: "Test".to_string(),
             algorithm: "SHA1".to_string(),
             digits: 6,
             period: 30,
             epoch: 0,
+            note: None,
+            favorite: false,
+            offset_seconds: 0,
         }
     }
 
@@ -929,7 +1910,12 @@ mod tests {
                 create_test_account("Google"),
                 create_test_account("Amazon"),
                 create_test_account("Microsoft"),
+                Account {
+                    digits: 8,
+                    ..create_test_account("Bank")
+                },
             ],
+            passphrase_hash: None,
         }
     }
 
@@ -995,6 +1981,31 @@ mod tests {
         }
     }
 
+    #[test]
+    fn test_screen_buffer_changed_rows() {
+        let mut buffer = ScreenBuffer::new(80, 4);
+        buffer.write_line(0, "Header".to_string());
+        buffer.write_line(2, "github".to_string());
+
+        // Nothing has been drawn yet, so every row is repainted
+        assert_eq!(buffer.changed_rows(), [0, 1, 2, 3]);
+        buffer.drawn = Some(buffer.lines.clone());
+
+        // Redrawing the same frame changes nothing
+        buffer.clear();
+        buffer.write_line(0, "Header".to_string());
+        buffer.write_line(2, "github".to_string());
+        assert!(buffer.changed_rows().is_empty());
+
+        // A changed style counts as much as changed text
+        buffer.write_highlighted_line(2, "github".to_string());
+        buffer.write_line(3, "gitlab".to_string());
+        assert_eq!(buffer.changed_rows(), [2, 3]);
+
+        buffer.invalidate();
+        assert_eq!(buffer.changed_rows().len(), 4);
+    }
+
     #[test]
     fn test_copied_state_tracking() {
         let mut copied_state = CopiedState::new();
@@ -1013,6 +2024,32 @@ mod tests {
         assert!(copied_state.is_recently_copied("Google"));
     }
 
+    #[test]
+    fn test_copied_value_expires_after_timeout() {
+        let mut copied_state = CopiedState::with_clear_after(Some(Duration::from_secs(20)));
+        copied_state.remember_copied_value("123456".to_string());
+        let copied_at = copied_state.pending_clear.as_ref().unwrap().1;
+
+        assert_eq!(
+            copied_state.take_expired_value(copied_at + Duration::from_secs(19)),
+            None
+        );
+        assert_eq!(
+            copied_state.take_expired_value(copied_at + Duration::from_secs(20)),
+            Some("123456".to_string())
+        );
+        // Only cleared once
+        assert_eq!(
+            copied_state.take_expired_value(copied_at + Duration::from_secs(30)),
+            None
+        );
+
+        // Nothing is remembered when clearing is turned off
+        let mut copied_state = CopiedState::new();
+        copied_state.remember_copied_value("123456".to_string());
+        assert!(copied_state.pending_clear.is_none());
+    }
+
     #[test]
     fn test_copied_state_cleanup() {
         let mut copied_state = CopiedState::new();
@@ -1044,11 +2081,26 @@ mod tests {
 
         let filtered = get_filtered_accounts(&storage, &mode, &matcher);
 
-        assert_eq!(filtered.len(), 4);
+        assert_eq!(filtered.len(), 5);
         assert_eq!(filtered[0].name, "GitHub");
         assert_eq!(filtered[1].name, "Google");
         assert_eq!(filtered[2].name, "Amazon");
         assert_eq!(filtered[3].name, "Microsoft");
+        assert_eq!(filtered[4].name, "Bank");
+    }
+
+    #[test]
+    fn test_get_filtered_accounts_pins_favorites_first() {
+        let mut storage = create_test_storage();
+        storage.accounts[2].favorite = true;
+        storage.accounts[4].favorite = true;
+        let matcher = SkimMatcherV2::default();
+
+        let names: Vec<_> = get_filtered_accounts(&storage, &DashboardMode::List, &matcher)
+            .iter()
+            .map(|account| account.name.as_str())
+            .collect();
+        assert_eq!(names, ["Amazon", "Bank", "GitHub", "Google", "Microsoft"]);
     }
 
     #[test]
@@ -1098,12 +2150,51 @@ mod tests {
         // Test Add mode
         let mode = DashboardMode::Add;
         let filtered = get_filtered_accounts(&storage, &mode, &matcher);
-        assert_eq!(filtered.len(), 4);
+        assert_eq!(filtered.len(), 5);
 
         // Test AddMethod mode
         let mode = DashboardMode::AddMethod;
         let filtered = get_filtered_accounts(&storage, &mode, &matcher);
-        assert_eq!(filtered.len(), 4);
+        assert_eq!(filtered.len(), 5);
+    }
+
+    #[test]
+    fn test_refreshed_storage_reflects_changes() {
+        let backend = InMemoryBackend::new(create_test_storage());
+        let matcher = SkimMatcherV2::default();
+        let mode = DashboardMode::Search("Git".to_string());
+
+        let storage = get_storage(&backend).unwrap();
+        assert_eq!(get_filtered_accounts(&storage, &mode, &matcher).len(), 1);
+
+        // What a delete followed by InputResult::RefreshStorage does
+        delete_accounts(&["GitHub"], &backend).unwrap();
+        let storage = get_storage(&backend).unwrap();
+        assert!(get_filtered_accounts(&storage, &mode, &matcher).is_empty());
+        assert_eq!(
+            get_filtered_accounts(&storage, &DashboardMode::List, &matcher).len(),
+            4
+        );
+    }
+
+    #[test]
+    fn test_dashboard_storage_uses_file_backend() {
+        let temp_dir = tempfile::TempDir::new().unwrap();
+        let path = temp_dir.path().join("accounts.json");
+        let backend = FileBackend::new(path.to_str().unwrap());
+
+        // Accounts added and deleted from the dashboard should land in the file
+        save_account(create_test_account("GitHub"), false, &backend).unwrap();
+        save_account(create_test_account("Amazon"), false, &backend).unwrap();
+        save_account(create_test_account("Google"), false, &backend).unwrap();
+        delete_accounts(&["Google"], &backend).unwrap();
+
+        let storage = get_storage(&backend).unwrap();
+        let matcher = SkimMatcherV2::default();
+        let filtered = get_filtered_accounts(&storage, &DashboardMode::List, &matcher);
+
+        let names: Vec<&str> = filtered.iter().map(|a| a.name.as_str()).collect();
+        assert_eq!(names, vec!["Amazon", "GitHub"]);
     }
 
     #[test]
@@ -1140,63 +2231,557 @@ mod tests {
     }
 
     #[test]
-    #[cfg(target_os = "macos")]
-    fn test_extract_account_from_otpauth_valid() {
-        let uri = "otpauth://totp/GitHub?secret=ABC123&issuer=GitHub";
-        let result = extract_account_from_otpauth(uri);
-        assert_eq!(result, Some("GitHub".to_string()));
+    fn test_next_frame_wait_lands_on_the_interval() {
+        let interval = Duration::from_millis(250);
+        let wait = next_frame_wait(Duration::from_millis(1_700_000_000_100), interval);
+        assert_eq!(wait, Duration::from_millis(150));
+        // Exactly on a frame waits a whole interval rather than not at all
+        let wait = next_frame_wait(Duration::from_secs(1_700_000_000), interval);
+        assert_eq!(wait, interval);
+        let wait = next_frame_wait(Duration::from_millis(2_500), Duration::from_secs(1));
+        assert_eq!(wait, Duration::from_millis(500));
+    }
+
+    #[test]
+    fn test_progress_line_uses_account_period() {
+        // 45s into a 60-second step: three quarters full, 15s left
+        let line = progress_line(60, 0, Duration::from_secs(105));
+        assert!(line.ends_with("| 15s"), "got '{}'", line);
+        assert!(line.starts_with(&format!("|{}", "█".repeat(22))));
+
+        // The same moment in a 30-second step has 15s left too, but half the bar
+        let line = progress_line(30, 0, Duration::from_secs(105));
+        assert!(line.ends_with("| 15s"), "got '{}'", line);
+        assert_eq!(line.matches('█').count(), 15);
+
+        let line = progress_line(60, 0, Duration::from_secs(61));
+        assert!(line.ends_with("| 59s"), "got '{}'", line);
+        assert_eq!(
+            progress_line(0, 0, Duration::from_secs(61)),
+            format!("|{}|  0s", " ".repeat(30))
+        );
+    }
+
+    #[test]
+    fn test_short_terminal_has_no_account_rows() {
+        let mut buffer = ScreenBuffer::new(80, 2);
+        assert_eq!(buffer.account_rows(), 0);
+
+        buffer.render_header(&DashboardMode::List, "");
+        buffer.render_progress_bar(None);
+
+        let mut selected = 0;
+        select_next(&mut selected, 5, buffer.height);
+        assert_eq!(selected, 0);
+    }
+
+    #[test]
+    fn test_status_line() {
+        assert_eq!(
+            status_line(57, 57, 20),
+            " 57 accounts, 37 more below — ↑↓ select, Enter copy"
+        );
+        assert_eq!(
+            status_line(3, 57, 20),
+            " 3/57 accounts — ↑↓ select, Enter copy"
+        );
+        assert_eq!(status_line(1, 1, 20), " 1 account — ↑↓ select, Enter copy");
+
+        let mut buffer = ScreenBuffer::new(80, 10);
+        buffer.render_status(0, 2);
+        assert!(buffer.lines[9].content.starts_with(" 0/2 accounts"));
+        assert_eq!(
+            buffer.lines[9].dim_ranges,
+            vec![0..buffer.lines[9].content.len()]
+        );
+
+        // No room below the progress bar
+        let mut buffer = ScreenBuffer::new(80, 4);
+        buffer.render_status(2, 2);
+        assert!(buffer.lines.iter().all(|line| line.content.is_empty()));
     }
 
     #[test]
-    #[cfg(target_os = "macos")]
-    fn test_extract_account_from_otpauth_with_encoded_name() {
-        let uri = "otpauth://totp/My%20Account?secret=ABC123";
-        let result = extract_account_from_otpauth(uri);
-        assert_eq!(result, Some("My Account".to_string()));
+    fn test_render_help() {
+        let mut buffer = ScreenBuffer::new(80, 20);
+        buffer.render_header(&DashboardMode::Help, "");
+        buffer.render_help();
+
+        let text: Vec<&str> = buffer
+            .lines
+            .iter()
+            .map(|line| line.content.as_str())
+            .collect();
+        assert!(text[0].starts_with("Help"));
+        assert!(text.iter().any(|line| line.starts_with(" Enter      Copy")));
+        assert!(
+            text.iter()
+                .any(|line| line.starts_with(" ?          Show this help"))
+        );
+        assert_eq!(
+            text.iter().any(|line| line.contains("[S]creenshot")),
+            cfg!(any(target_os = "macos", target_os = "linux", windows))
+        );
+        assert_eq!(
+            &buffer.lines[3].content[buffer.lines[3].dim_ranges[0].clone()],
+            "Enter"
+        );
     }
 
     #[test]
-    #[cfg(target_os = "macos")]
-    fn test_extract_account_from_otpauth_no_query() {
-        let uri = "otpauth://totp/SimpleAccount";
-        let result = extract_account_from_otpauth(uri);
-        assert_eq!(result, Some("SimpleAccount".to_string()));
+    fn test_select_next_stops_at_visible_rows() {
+        let mut selected = 0;
+        // Three of the eight rows are left for accounts
+        for _ in 0..10 {
+            select_next(&mut selected, 5, 8);
+        }
+        assert_eq!(selected, 2);
     }
 
     #[test]
-    #[cfg(target_os = "macos")]
-    fn test_extract_account_from_otpauth_invalid() {
-        let uri = "invalid://uri";
-        let result = extract_account_from_otpauth(uri);
-        assert_eq!(result, None);
+    fn test_label_with_note() {
+        assert_eq!(label_with_note("github", None, 30), "github");
 
-        let uri = "otpauth://hotp/Account"; // Wrong type
-        let result = extract_account_from_otpauth(uri);
-        assert_eq!(result, None);
+        let note = Some("backup codes in 1Password");
+        assert_eq!(
+            label_with_note("github", note, 40),
+            "github (backup codes in 1Password)"
+        );
+        assert_eq!(label_with_note("github", note, 20), "github (backup c...)");
+        // No room for the note at all
+        assert_eq!(label_with_note("github", note, 12), "github");
+    }
+
+    #[test]
+    fn test_issuer_prefix() {
+        let mut account = create_test_account("john@example.com");
+        account.issuer = "GitHub".to_string();
+        assert_eq!(issuer_prefix(&account), "GitHub · ");
+
+        account.issuer = DEFAULT_ISSUER.to_string();
+        assert_eq!(issuer_prefix(&account), "");
+        account.issuer = String::new();
+        assert_eq!(issuer_prefix(&account), "");
+    }
+
+    #[test]
+    fn test_render_account_line_underlines_matches() {
+        let mut buffer = ScreenBuffer::new(80, 10);
+        let mut account = create_test_account("github");
+        account.issuer = "GitHub".to_string();
+        let (_, matched) = SkimMatcherV2::default()
+            .fuzzy_indices(&account.name, "gth")
+            .unwrap();
+
+        buffer
+            .render_account_line(
+                &account,
+                4,
+                false,
+                false,
+                &CopiedState::new(),
+                &DisplayOptions::default(),
+                &mut CodeCache::default(),
+                &matched,
+            )
+            .unwrap();
+        let line = &buffer.lines[4];
+        let underlined: Vec<&str> = line
+            .match_ranges
+            .iter()
+            .map(|range| &line.content[range.clone()])
+            .collect();
+        assert_eq!(underlined, ["g", "t", "h"]);
+        // The ranges are within the name, after the dimmed issuer
+        assert!(line.match_ranges[0].start >= line.dim_ranges[0].end);
+
+        // Characters cut off by truncation aren't marked
+        let mut buffer = ScreenBuffer::new(40, 10);
+        let account = create_test_account("a-very-long-account-name-xyz");
+        let display = DisplayOptions {
+            show_issuer: false,
+            ..DisplayOptions::default()
+        };
+        buffer
+            .render_account_line(
+                &account,
+                4,
+                false,
+                false,
+                &CopiedState::new(),
+                &display,
+                &mut CodeCache::default(),
+                &[0, 27],
+            )
+            .unwrap();
+        assert!(buffer.lines[4].content.starts_with(" a-very..."));
+        assert_eq!(buffer.lines[4].match_ranges, vec![1..2]);
+    }
+
+    #[test]
+    fn test_render_account_line_dims_issuer() {
+        let mut buffer = ScreenBuffer::new(80, 10);
+        let mut account = create_test_account("john@example.com");
+        account.issuer = "GitHub".to_string();
+
+        let display = DisplayOptions::default();
+        buffer
+            .render_account_line(
+                &account,
+                4,
+                false,
+                false,
+                &CopiedState::new(),
+                &display,
+                &mut CodeCache::default(),
+                &[],
+            )
+            .unwrap();
+        let line = &buffer.lines[4];
+        assert!(line.content.starts_with(" GitHub · john@example.com"));
+        assert_eq!(&line.content[line.dim_ranges[0].clone()], "GitHub · ");
+
+        // Codes stay in the same column with or without the issuer
+        let code_column = |line: &str| line[..line.rfind(char::is_numeric).unwrap()].width();
+        let with_issuer = code_column(&line.content);
+        buffer.clear();
+        let display = DisplayOptions {
+            show_issuer: false,
+            ..DisplayOptions::default()
+        };
+        buffer
+            .render_account_line(
+                &account,
+                4,
+                false,
+                false,
+                &CopiedState::new(),
+                &display,
+                &mut CodeCache::default(),
+                &[],
+            )
+            .unwrap();
+        assert!(buffer.lines[4].dim_ranges.is_empty());
+        assert_eq!(code_column(&buffer.lines[4].content), with_issuer);
+    }
+
+    #[test]
+    fn test_render_account_line_marks_selection() {
+        let mut buffer = ScreenBuffer::new(80, 10);
+        let mut account = create_test_account("john@example.com");
+        account.issuer = "GitHub".to_string();
+        account.favorite = true;
+
+        buffer
+            .render_account_line(
+                &account,
+                4,
+                false,
+                true,
+                &CopiedState::new(),
+                &DisplayOptions::default(),
+                &mut CodeCache::default(),
+                &[],
+            )
+            .unwrap();
+        let line = &buffer.lines[4];
+        assert!(line.content.starts_with(" ✓ ★ GitHub · john@example.com"));
+        assert_eq!(&line.content[line.dim_ranges[0].clone()], "GitHub · ");
+    }
+
+    #[test]
+    fn test_toggle_mark() {
+        let mut marked = HashSet::new();
+        toggle_mark(&mut marked, "GitHub");
+        toggle_mark(&mut marked, "Google");
+        toggle_mark(&mut marked, "GitHub");
+        assert_eq!(marked, HashSet::from(["Google".to_string()]));
+    }
+
+    #[test]
+    fn test_revealed_secret_hides_after_a_while() {
+        let shown_at = Instant::now();
+        let revealed = RevealedSecret {
+            name: "GitHub".to_string(),
+            secret: Zeroizing::new("JBSWY3DPEHPK3PXP".to_string()),
+            shown_at,
+        };
+        assert_eq!(
+            revealed.status_line(shown_at + Duration::from_millis(500)),
+            " Secret for GitHub: JBSWY3DPEHPK3PXP (hidden in 10s)"
+        );
+        assert!(!revealed.is_expired(shown_at + Duration::from_secs(9)));
+        assert!(revealed.is_expired(shown_at + REVEAL_DURATION));
+
+        let mut buffer = ScreenBuffer::new(80, 10);
+        buffer.render_revealed_secret(&revealed, shown_at);
+        assert!(buffer.lines[9].content.contains("JBSWY3DPEHPK3PXP"));
+        buffer.clear();
+        buffer.render_status(2, 2);
+        assert!(!buffer.lines[9].content.contains("JBSWY3DPEHPK3PXP"));
+    }
+
+    #[test]
+    fn test_delete_prompt() {
+        assert_eq!(
+            delete_prompt(&["GitHub"]),
+            "Delete account 'GitHub'? [y/N] "
+        );
+        assert_eq!(
+            delete_prompt(&["GitHub", "Google", "Amazon"]),
+            "Delete 3 accounts? [y/N] "
+        );
+    }
+
+    #[test]
+    fn test_code_cache_reuses_decoded_secret() {
+        let mut codes = CodeCache::default();
+        let mut account = create_test_account("github");
+        let original = account.clone();
+        let now = Duration::from_secs(59);
+        let later = now + Duration::from_secs(30);
+
+        codes.generate(&account, now).unwrap();
+        assert_eq!(codes.accounts.len(), 1);
+
+        // A cache hit uses the decoded key, not the account's current secret
+        account.secret = "GEZDGNBVGY3TQOJQ".into();
+        assert_eq!(
+            codes.generate(&account, later).unwrap().code,
+            generate_totp(&original, later).unwrap()
+        );
+        assert_eq!(codes.accounts.len(), 1);
+
+        codes.clear();
+        assert_eq!(
+            codes.generate(&account, later).unwrap().code,
+            generate_totp(&account, later).unwrap()
+        );
+    }
+
+    #[test]
+    fn test_code_cache_regenerates_only_on_step_change() {
+        let mut codes = CodeCache::default();
+        let account = create_test_account("github");
+
+        let first = codes.generate(&account, Duration::from_secs(30)).unwrap();
+        // With the key gone, only cached codes can be returned
+        let key = std::mem::take(&mut codes.accounts.get_mut("github").unwrap().key);
+        for secs in [31, 45, 59] {
+            assert_eq!(
+                codes.generate(&account, Duration::from_secs(secs)).unwrap(),
+                first
+            );
+        }
+        assert_eq!(codes.accounts["github"].codes.len(), 1);
+
+        codes.accounts.get_mut("github").unwrap().key = key;
+        let next = codes.generate(&account, Duration::from_secs(60)).unwrap();
+        assert_eq!(next.step, first.step + 1);
+        assert_eq!(
+            next.code,
+            generate_totp(&account, Duration::from_secs(60)).unwrap()
+        );
+
+        // Steps further away are dropped so the cache stays small
+        codes.generate(&account, Duration::from_secs(90)).unwrap();
+        let steps: Vec<u64> = codes.accounts["github"]
+            .codes
+            .iter()
+            .map(|c| c.step)
+            .collect();
+        assert_eq!(steps, [2, 3]);
+    }
+
+    #[test]
+    fn test_render_account_line_with_next_code() {
+        let mut buffer = ScreenBuffer::new(80, 10);
+        let account = create_test_account("github");
+        let display = DisplayOptions {
+            show_next: true,
+            ..DisplayOptions::default()
+        };
+
+        let now = SystemTime::now().duration_since(UNIX_EPOCH).unwrap();
+        buffer
+            .render_account_line(
+                &account,
+                4,
+                false,
+                false,
+                &CopiedState::new(),
+                &display,
+                &mut CodeCache::default(),
+                &[],
+            )
+            .unwrap();
+        let line = &buffer.lines[4];
+        let next = generate_totp(&account, now + Duration::from_secs(30)).unwrap();
+        let dimmed = &line.content[line.dim_ranges.last().unwrap().clone()];
+
+        // Allow for the period rolling over between computing the two codes
+        let after = generate_totp(&account, now + Duration::from_secs(60)).unwrap();
+        assert!(
+            dimmed == account.format_code(next) || dimmed == account.format_code(after),
+            "Dimmed text should be the next code, got '{}'",
+            dimmed
+        );
+    }
+
+    #[test]
+    fn test_render_account_line_mixed_digits() {
+        let storage = create_test_storage();
+        let six = &storage.accounts[0];
+        let eight = storage.accounts.iter().find(|a| a.digits == 8).unwrap();
+        let mut buffer = ScreenBuffer::new(80, 10);
+        let display = DisplayOptions::default();
+        for (row, account) in [(1, six), (2, eight)] {
+            buffer
+                .render_account_line(
+                    account,
+                    row,
+                    false,
+                    false,
+                    &CopiedState::new(),
+                    &display,
+                    &mut CodeCache::default(),
+                    &[],
+                )
+                .unwrap();
+        }
+
+        let code_of = |line: &str| {
+            line.split_whitespace()
+                .last()
+                .filter(|word| word.chars().all(|c| c.is_ascii_digit()))
+                .map(str::to_string)
+                .unwrap()
+        };
+        assert_eq!(code_of(&buffer.lines[1].content).len(), 6);
+        // Leading zeros are kept so the full 8-digit code is shown and copied
+        let code = code_of(&buffer.lines[2].content);
+        assert_eq!(code.len(), 8);
+        let now = SystemTime::now().duration_since(UNIX_EPOCH).unwrap();
+        let expected = eight.format_code(generate_totp(eight, now).unwrap());
+        let previous =
+            eight.format_code(generate_totp(eight, now - Duration::from_secs(30)).unwrap());
+        assert!(code == expected || code == previous);
+
+        // Codes of different lengths end in the same column
+        let code_end = |line: &str| line.rfind(char::is_numeric).unwrap();
+        assert_eq!(
+            code_end(&buffer.lines[1].content),
+            code_end(&buffer.lines[2].content)
+        );
+    }
+
+    #[test]
+    fn test_render_account_line_grouped() {
+        let account = create_test_account("github");
+        let mut buffer = ScreenBuffer::new(80, 10);
+        for (row, group_codes) in [(1, false), (2, true)] {
+            let display = DisplayOptions {
+                group_codes,
+                ..DisplayOptions::default()
+            };
+            buffer
+                .render_account_line(
+                    &account,
+                    row,
+                    false,
+                    false,
+                    &CopiedState::new(),
+                    &display,
+                    &mut CodeCache::default(),
+                    &[],
+                )
+                .unwrap();
+        }
+
+        let words = |line: &str| {
+            line.split_whitespace()
+                .map(str::to_string)
+                .collect::<Vec<_>>()
+        };
+        let plain = words(&buffer.lines[1].content);
+        let grouped = words(&buffer.lines[2].content);
+        assert_eq!(grouped.len(), plain.len() + 1);
+        let [first, second] = &grouped[grouped.len() - 2..] else {
+            unreachable!()
+        };
+        assert_eq!((first.len(), second.len()), (3, 3));
+
+        // Grouping takes its space from the name column, so codes still line up
+        let code_end = |line: &str| line.rfind(char::is_numeric).unwrap();
+        assert_eq!(
+            code_end(&buffer.lines[1].content),
+            code_end(&buffer.lines[2].content)
+        );
+    }
+
+    #[test]
+    fn test_truncate_to_width() {
+        assert_eq!(truncate_to_width("github", 10), "github");
+        assert_eq!(truncate_to_width("a-very-long-name", 10), "a-very-...");
+        // Double-width glyphs take two columns each
+        assert_eq!(truncate_to_width("日本語のアカウント", 9), "日本語...");
+        assert_eq!(truncate_to_width("🔐🔐🔐🔐", 6), "🔐...");
+    }
+
+    #[test]
+    fn test_render_account_line_with_long_multibyte_name() {
+        let mut buffer = ScreenBuffer::new(40, 10);
+        let account = create_test_account("用户名称非常长的账户名称用户名称非常长的账户名称🔐🔐🔐");
+
+        buffer
+            .render_account_line(
+                &account,
+                4,
+                true,
+                false,
+                &CopiedState::new(),
+                &DisplayOptions::default(),
+                &mut CodeCache::default(),
+                &[],
+            )
+            .expect("Rendering should not panic on multibyte names");
+        assert!(buffer.lines[4].content.contains("..."));
     }
 
     #[test]
-    #[cfg(target_os = "macos")]
-    fn test_extract_secret_from_otpauth_valid() {
-        let uri = "otpauth://totp/GitHub?secret=ABC123&issuer=GitHub";
-        let result = extract_secret_from_otpauth(uri);
-        assert_eq!(result, Some("ABC123".to_string()));
+    fn test_apply_account_edits() {
+        let mut account = create_test_account("github");
+
+        apply_account_edits(&mut account, "GitHub", "sha256", "8", "60").unwrap();
+        assert_eq!(account.issuer, "GitHub");
+        assert_eq!(account.algorithm, "SHA256");
+        assert_eq!(account.digits, 8);
+        assert_eq!(account.period, 60);
+
+        // Empty answers keep the current values
+        apply_account_edits(&mut account, "", "", "", "").unwrap();
+        assert_eq!(account.algorithm, "SHA256");
+        assert_eq!(account.period, 60);
     }
 
     #[test]
-    #[cfg(target_os = "macos")]
-    fn test_extract_secret_from_otpauth_no_secret() {
-        let uri = "otpauth://totp/GitHub?issuer=GitHub";
-        let result = extract_secret_from_otpauth(uri);
-        assert_eq!(result, None);
+    fn test_apply_account_edits_rejects_invalid_values() {
+        let mut account = create_test_account("github");
+
+        assert!(apply_account_edits(&mut account, "", "MD5", "", "").is_err());
+        assert!(apply_account_edits(&mut account, "", "", "12", "").is_err());
+        assert!(apply_account_edits(&mut account, "", "", "0", "").is_err());
+        assert!(apply_account_edits(&mut account, "", "", "", "0").is_err());
+        assert!(apply_account_edits(&mut account, "", "", "", "abc").is_err());
     }
 
     #[test]
-    #[cfg(target_os = "macos")]
-    fn test_extract_secret_from_otpauth_invalid_uri() {
-        let uri = "invalid://uri";
-        let result = extract_secret_from_otpauth(uri);
-        assert_eq!(result, None);
+    fn test_image_path() {
+        assert_eq!(image_path("  /tmp/qr.png\n"), "/tmp/qr.png");
+        assert_eq!(image_path("'/tmp/my qr.png'\n"), "/tmp/my qr.png");
+        assert_eq!(image_path("\"/tmp/my qr.png\""), "/tmp/my qr.png");
+        assert_eq!(image_path("'"), "'");
+        assert_eq!(image_path("\n"), "");
     }
 
     #[test]
@@ -0,0 +1,220 @@
+use serde_json::Value;
+
+use crate::AppError;
+use crate::totp::Account;
+
+/// Where to find accounts in a JSON export. Paths are dotted field names,
+/// where a number indexes an array and `*` stands for every element.
+pub struct JsonPaths<'a> {
+    /// The array of items, or the whole document when `None`
+    pub items: Option<&'a str>,
+    /// The otpauth:// URI, or a bare Base32 secret, within each item
+    pub totp: &'a str,
+    /// The account name within each item, used instead of the URI's label
+    pub name: Option<&'a str>,
+}
+
+/// Extracts accounts from a JSON export, such as a password manager's, that
+/// keeps an otpauth:// URI in each item. Items without one are ignored, as
+/// most logins don't have TOTP set up.
+///
+/// Returns the accounts along with a description of each item whose TOTP
+/// couldn't be used.
+pub fn parse_accounts_json(
+    json: &str,
+    paths: &JsonPaths,
+) -> Result<(Vec<Account>, Vec<String>), AppError> {
+    let root: Value = serde_json::from_str(json)?;
+    let found = match paths.items {
+        Some(path) => lookup(&root, path),
+        None => vec![&root],
+    };
+    let arrays: Vec<&Vec<Value>> = found.into_iter().filter_map(Value::as_array).collect();
+    if arrays.is_empty() {
+        return Err(AppError::InvalidInput(match paths.items {
+            Some(path) => format!("No array of items found at '{}'", path),
+            None => "Expected an array of items; use --items-path to find it".to_string(),
+        }));
+    }
+
+    let mut accounts = Vec::new();
+    let mut skipped = Vec::new();
+    for (index, item) in arrays.into_iter().flatten().enumerate() {
+        let Some(totp) = first_string(item, paths.totp) else {
+            continue;
+        };
+        let name = paths.name.and_then(|path| first_string(item, path));
+        let result = if totp.starts_with("otpauth://") {
+            crate::warn_issuer_mismatch(totp);
+            Account::from_otpauth_uri(totp)
+        } else if let Some(name) = name {
+            // Bitwarden also accepts a plain secret in its TOTP field
+            Ok(Account::new(name.to_string(), totp.to_string()))
+        } else {
+            Err(AppError::InvalidInput(
+                "a bare secret needs a name; use --name-path".to_string(),
+            ))
+        };
+        match result {
+            Ok(mut account) => {
+                if let Some(name) = name {
+                    account.name = name.to_string();
+                }
+                accounts.push(account);
+            }
+            Err(e) => skipped.push(format!(
+                "{}: {}",
+                name.map_or_else(
+                    || format!("item {}", index + 1),
+                    |name| format!("'{}'", name)
+                ),
+                e
+            )),
+        }
+    }
+
+    Ok((accounts, skipped))
+}
+
+/// Every value reached by following the dotted `path` from `value`.
+fn lookup<'a>(value: &'a Value, path: &str) -> Vec<&'a Value> {
+    let mut values = vec![value];
+    for segment in path.split('.') {
+        values = values
+            .into_iter()
+            .flat_map(|value| -> Vec<&Value> {
+                match (value, segment) {
+                    (Value::Array(items), "*") => items.iter().collect(),
+                    (Value::Object(fields), "*") => fields.values().collect(),
+                    (Value::Array(items), _) => segment
+                        .parse::<usize>()
+                        .ok()
+                        .and_then(|index| items.get(index))
+                        .into_iter()
+                        .collect(),
+                    _ => value.get(segment).into_iter().collect(),
+                }
+            })
+            .collect();
+    }
+    values
+}
+
+/// The first non-empty string at `path`, trimmed.
+fn first_string<'a>(value: &'a Value, path: &str) -> Option<&'a str> {
+    lookup(value, path)
+        .into_iter()
+        .filter_map(Value::as_str)
+        .map(str::trim)
+        .find(|s| !s.is_empty())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const BITWARDEN_EXPORT: &str = r#"{
+        "encrypted": false,
+        "folders": [],
+        "items": [
+            {
+                "type": 1,
+                "name": "GitHub",
+                "login": {
+                    "username": "alice",
+                    "password": "hunter2",
+                    "totp": "otpauth://totp/GitHub:alice?secret=JBSWY3DPEHPK3PXP&issuer=GitHub&digits=8"
+                }
+            },
+            { "type": 1, "name": "No TOTP", "login": { "username": "bob", "totp": null } },
+            { "type": 1, "name": "Plain", "login": { "totp": "JBSWY3DPEHPK3PXP" } },
+            { "type": 1, "name": "Broken", "login": { "totp": "otpauth://totp/x?issuer=Nope" } },
+            { "type": 2, "name": "Note", "notes": "no login at all" }
+        ]
+    }"#;
+
+    // Trimmed down from the export.data file inside a 1Password .1pux export
+    const ONEPASSWORD_EXPORT: &str = r#"{
+        "accounts": [{
+            "vaults": [{
+                "items": [{
+                    "overview": { "title": "Google" },
+                    "details": {
+                        "sections": [
+                            { "fields": [{ "title": "notes", "value": { "string": "" } }] },
+                            { "fields": [{
+                                "title": "one-time password",
+                                "value": { "totp": "otpauth://totp/google?secret=JBSWY3DPEHPK3PXP&algorithm=SHA256" }
+                            }] }
+                        ]
+                    }
+                }]
+            }]
+        }]
+    }"#;
+
+    #[test]
+    fn test_parse_bitwarden_export() {
+        let paths = JsonPaths {
+            items: Some("items"),
+            totp: "login.totp",
+            name: Some("name"),
+        };
+        let (accounts, skipped) = parse_accounts_json(BITWARDEN_EXPORT, &paths).unwrap();
+
+        let names: Vec<_> = accounts.iter().map(|a| a.name.as_str()).collect();
+        assert_eq!(names, ["GitHub", "Plain"]);
+        assert_eq!(accounts[0].issuer, "GitHub");
+        assert_eq!(accounts[0].digits, 8);
+        assert_eq!(skipped.len(), 1);
+        assert!(skipped[0].starts_with("'Broken': "), "{}", skipped[0]);
+    }
+
+    #[test]
+    fn test_parse_onepassword_export() {
+        let paths = JsonPaths {
+            items: Some("accounts.*.vaults.*.items"),
+            totp: "details.sections.*.fields.*.value.totp",
+            name: Some("overview.title"),
+        };
+        let (accounts, skipped) = parse_accounts_json(ONEPASSWORD_EXPORT, &paths).unwrap();
+
+        assert!(skipped.is_empty());
+        assert_eq!(accounts.len(), 1);
+        assert_eq!(accounts[0].name, "Google");
+        assert_eq!(accounts[0].algorithm, "SHA256");
+    }
+
+    #[test]
+    fn test_parse_accounts_json_without_items() {
+        let paths = JsonPaths {
+            items: None,
+            totp: "totp",
+            name: None,
+        };
+        let json = r#"[{"totp": "otpauth://totp/github?secret=JBSWY3DPEHPK3PXP"}, {"totp": "JBSWY3DPEHPK3PXP"}]"#;
+        let (accounts, skipped) = parse_accounts_json(json, &paths).unwrap();
+        assert_eq!(accounts[0].name, "github");
+        assert_eq!(
+            skipped,
+            ["item 2: a bare secret needs a name; use --name-path"]
+        );
+
+        assert!(parse_accounts_json(BITWARDEN_EXPORT, &paths).is_err());
+        let paths = JsonPaths {
+            items: Some("folders.0"),
+            ..paths
+        };
+        assert!(parse_accounts_json(BITWARDEN_EXPORT, &paths).is_err());
+    }
+
+    #[test]
+    fn test_lookup() {
+        let value: Value =
+            serde_json::from_str(r#"{"a": [{"b": 1}, {"b": 2}, {"c": 3}]}"#).unwrap();
+        assert_eq!(lookup(&value, "a.*.b"), [&Value::from(1), &Value::from(2)]);
+        assert_eq!(lookup(&value, "a.1.b"), [&Value::from(2)]);
+        assert!(lookup(&value, "a.5.b").is_empty());
+        assert!(lookup(&value, "x.y").is_empty());
+    }
+}
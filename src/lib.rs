@@ -1,42 +1,123 @@
-mod totp;
+pub mod totp;
 
-pub use totp::{Account, generate_otpauth_uri, generate_totp};
+pub use totp::{
+    Account, AccountBuilder, STEAM_ALGORITHM, Secret, SecretEncoding, TotpCode, decode_secret,
+    generate_otpauth_uri, generate_totp, generate_totp_for_step, generate_totp_full,
+    generate_totp_with_key, normalize_algorithm, normalize_secret, validate_secret, verify_totp,
+};
 
 #[derive(Debug)]
-pub struct AppError {
-    message: String,
+pub enum AppError {
+    /// No account with the given name exists
+    NotFound(String),
+    /// A secret is empty or is not valid Base32
+    InvalidSecret,
+    /// A URI, file, or argument supplied by the user could not be used
+    InvalidInput(String),
+    /// Reading, writing, or locking the storage file failed
+    Storage(String),
+    Serialization(serde_json::Error),
+    Io(std::io::Error),
+    Keyring(keyring::Error),
+    Other(String),
 }
 
 impl AppError {
     pub fn new(message: impl Into<String>) -> Self {
-        Self {
-            message: message.into(),
+        Self::Other(message.into())
+    }
+}
+
+impl AppError {
+    /// Process exit status for this kind of failure. These values are part of
+    /// the CLI's interface, so scripts can rely on them.
+    pub fn exit_code(&self) -> i32 {
+        match self {
+            Self::NotFound(_) => 2,
+            Self::InvalidSecret | Self::InvalidInput(_) => 3,
+            Self::Serialization(_) | Self::Io(_) | Self::Storage(_) => 4,
+            Self::Keyring(_) => 5,
+            Self::Other(_) => 1,
         }
     }
 }
 
 impl std::fmt::Display for AppError {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
-        write!(f, "{}", self.message)
+        match self {
+            Self::NotFound(name) => write!(f, "Account '{}' not found", name),
+            Self::InvalidSecret => write!(f, "Secret is not valid Base32"),
+            Self::Serialization(_) => write!(f, "JSON error"),
+            Self::Io(_) => write!(f, "IO error"),
+            Self::Keyring(_) => write!(f, "Keyring error"),
+            Self::InvalidInput(message) | Self::Storage(message) | Self::Other(message) => {
+                write!(f, "{}", message)
+            }
+        }
     }
 }
 
-impl std::error::Error for AppError {}
+impl std::error::Error for AppError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            Self::Serialization(err) => Some(err),
+            Self::Io(err) => Some(err),
+            Self::Keyring(err) => Some(err),
+            Self::NotFound(_)
+            | Self::InvalidSecret
+            | Self::InvalidInput(_)
+            | Self::Storage(_)
+            | Self::Other(_) => None,
+        }
+    }
+}
 
 impl From<keyring::Error> for AppError {
     fn from(err: keyring::Error) -> Self {
-        Self::new(format!("Keyring error: {}", err))
+        Self::Keyring(err)
     }
 }
 
 impl From<serde_json::Error> for AppError {
     fn from(err: serde_json::Error) -> Self {
-        Self::new(format!("Serialization error: {}", err))
+        Self::Serialization(err)
     }
 }
 
 impl From<std::io::Error> for AppError {
     fn from(err: std::io::Error) -> Self {
-        Self::new(format!("IO error: {}", err))
+        Self::Io(err)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::error::Error;
+
+    #[test]
+    fn test_from_keeps_source() {
+        let io_err = std::io::Error::new(std::io::ErrorKind::PermissionDenied, "denied");
+        let err = AppError::from(io_err);
+
+        assert!(matches!(err, AppError::Io(_)));
+        assert_eq!(err.to_string(), "IO error");
+        assert_eq!(err.source().unwrap().to_string(), "denied");
+        assert!(AppError::new("plain").source().is_none());
+    }
+
+    #[test]
+    fn test_exit_codes() {
+        assert_eq!(AppError::NotFound("github".to_string()).exit_code(), 2);
+        assert_eq!(AppError::InvalidSecret.exit_code(), 3);
+        assert_eq!(AppError::Storage("locked".to_string()).exit_code(), 4);
+        assert_eq!(AppError::Keyring(keyring::Error::NoEntry).exit_code(), 5);
+        assert_eq!(AppError::new("cancelled").exit_code(), 1);
+    }
+
+    #[test]
+    fn test_not_found_display() {
+        let err = AppError::NotFound("github".to_string());
+        assert_eq!(err.to_string(), "Account 'github' not found");
     }
 }
@@ -1,232 +1,1945 @@
-use clap::{Parser, Subcommand};
-use keyring::Entry;
+#[cfg(feature = "clipboard")]
+use arboard::Clipboard;
+use clap::{ArgGroup, Args, CommandFactory, Parser, Subcommand, ValueEnum};
+use clap_complete::Shell;
+#[cfg(feature = "dashboard")]
+use crossterm::{
+    cursor::{Hide, MoveToColumn, Show},
+    event::{Event, KeyCode, KeyModifiers, poll, read},
+    queue,
+    style::Print,
+    terminal::{Clear, ClearType, disable_raw_mode, enable_raw_mode},
+};
+use fuzzy_matcher::FuzzyMatcher;
+use fuzzy_matcher::skim::SkimMatcherV2;
 use rpassword::prompt_password;
-use serde::{Deserialize, Serialize};
+use serde::Serialize;
 use std::error::Error;
 use std::fs;
-use std::io::{self, Write};
+use std::io::{self, IsTerminal, Write};
+use std::ops::RangeInclusive;
 use std::path::Path;
-use std::time::{SystemTime, UNIX_EPOCH};
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+use time::OffsetDateTime;
+use time::format_description::well_known::Rfc3339;
+use zeroize::Zeroizing;
 
+mod aegis;
+mod andotp;
+#[cfg(feature = "dashboard")]
+mod biometrics;
+mod config;
+mod csv_accounts;
+#[cfg(feature = "dashboard")]
 mod dashboard;
-mod totp;
-use crate::totp::{Account, generate_otpauth_uri, generate_totp};
-use hotpot::AppError;
+mod json_accounts;
+mod migration;
+mod ntp;
+mod passphrase;
+mod profile;
+#[cfg(feature = "qr")]
+mod qr;
+mod storage;
+#[cfg(feature = "dashboard")]
+mod theme;
+use crate::totp::{
+    Account, MAX_DIGITS, MAX_WINDOW, MIN_DIGITS, STEAM_ALGORITHM, SecretEncoding, decode_secret,
+    generate_totp_for_step, generate_totp_full, normalize_algorithm, validate_secret, verify_totp,
+};
+use config::Config;
+use hotpot::{AppError, totp};
+use storage::{
+    FileBackend, KeyringBackend, KeyringEntriesBackend, Storage, StorageBackend, get_storage,
+    update_storage, write_file_atomically,
+};
 
 const SERVICE_NAME: &str = "hotpot";
 const STORAGE_KEY: &str = "_hotpot_storage";
 
+const EXIT_CODES_HELP: &str = "Exit codes:
+  0  Success
+  1  Other error
+  2  Account not found
+  3  Invalid input (bad secret, URI, file or arguments)
+  4  Storage or I/O error
+  5  Keyring error";
+
 #[derive(Parser)]
 #[command(name = "hotpot")]
 #[command(about = "A simple CLI for TOTP-based 2FA", long_about = None)]
+#[command(after_help = EXIT_CODES_HELP)]
 struct Cli {
-    /// Use file-backed storage instead of secure keyring storage
-    #[arg(short = 'f', long = "file", value_name = "FILE_PATH")]
-    file: Option<String>,
+    /// Use file-backed storage instead of secure keyring storage. Without a
+    /// path, uses <config dir>/hotpot/<profile>.json. Defaults to HOTPOT_FILE
+    /// when that is set
+    #[arg(short = 'f', long = "file", value_name = "FILE_PATH", num_args = 0..=1, global = true)]
+    file: Option<Option<String>>,
+
+    /// Keep accounts in a separate named store
+    #[arg(long, value_name = "NAME", env = "HOTPOT_PROFILE", global = true)]
+    profile: Option<String>,
+
+    /// Keyring service holding the accounts, so separate installs or test
+    /// runs can keep their own vaults
+    #[arg(long, value_name = "NAME", env = "HOTPOT_SERVICE", global = true)]
+    service: Option<String>,
+
+    /// Disable colors and other styling in the dashboard. Also set by NO_COLOR
+    #[arg(long, global = true)]
+    no_color: bool,
+
+    /// Clear a code copied in the dashboard after this many seconds, unless
+    /// something else has been copied since. 0 leaves it on the clipboard.
+    /// Defaults to 20
+    #[arg(long, value_name = "SECONDS")]
+    clipboard_clear_seconds: Option<u64>,
+
+    /// How often the dashboard redraws, in milliseconds. Longer intervals
+    /// save battery at the cost of a choppier progress bar. Defaults to 250
+    #[arg(long, value_name = "MS", value_parser = clap::value_parser!(u64).range(REFRESH_MS_RANGE))]
+    refresh_ms: Option<u64>,
+
+    /// Print the otpauth URI, which contains the secret, under QR codes
+    /// exported from the dashboard
+    #[arg(long)]
+    show_uri: bool,
+
+    /// Show codes in two groups, such as 123 456. Copied codes and JSON
+    /// output are never grouped
+    #[arg(long, global = true)]
+    group: bool,
 
     #[command(subcommand)]
     command: Option<Commands>,
 }
 
+#[derive(Subcommand)]
+enum ProfileCommands {
+    /// List profiles that have stored accounts
+    #[command(after_help = EXIT_CODES_HELP)]
+    List,
+}
+
+/// Overrides for the defaults used by `Account::builder`
+#[derive(Args, Default)]
+struct AccountOptions {
+    /// Hash algorithm used to generate codes: SHA1, SHA256 or SHA512
+    #[arg(long, value_parser = parse_hash_algorithm, conflicts_with_all = ["image", "uri", "from_clipboard"])]
+    algorithm: Option<String>,
+    /// Number of digits in each code
+    #[arg(long, value_parser = clap::value_parser!(u32).range(i64::from(MIN_DIGITS)..=i64::from(MAX_DIGITS)), conflicts_with_all = ["image", "uri", "from_clipboard"])]
+    digits: Option<u32>,
+    /// Seconds each code is valid for
+    #[arg(long, value_parser = clap::value_parser!(u32).range(1..), conflicts_with_all = ["image", "uri", "from_clipboard"])]
+    period: Option<u32>,
+    /// Service that issued the secret
+    #[arg(long, conflicts_with_all = ["image", "uri", "from_clipboard"])]
+    issuer: Option<String>,
+    /// Time codes are counted from (T0), as an RFC 3339 date such as
+    /// 2024-01-01T00:00:00Z. Defaults to the Unix epoch
+    #[arg(long, value_name = "DATE", value_parser = parse_epoch_date, conflicts_with_all = ["image", "uri", "from_clipboard"])]
+    epoch_date: Option<u64>,
+    /// Free-form note to keep with the account
+    #[arg(long)]
+    note: Option<String>,
+    /// How the secret is written: base32, or hex for keys only given that way
+    #[arg(long, conflicts_with_all = ["image", "uri", "from_clipboard"])]
+    encoding: Option<SecretEncoding>,
+    /// Add the account without asking, even if its secret looks too short
+    #[arg(long)]
+    allow_short: bool,
+}
+
+/// Secrets shorter than this many bytes were most likely cut off when copied.
+/// RFC 4226 requires at least 16, but some services use less.
+const MIN_SECRET_BYTES: usize = 10;
+
+/// Whether `add` may replace an account that already has the same name
+#[derive(Args, Default)]
+struct ReplaceOptions {
+    /// Replace an existing account with the same name, e.g. after its secret
+    /// was reset
+    #[arg(long)]
+    force: bool,
+    /// With --force, replace the account without asking for confirmation
+    #[arg(short = 'y', long, requires = "force")]
+    yes: bool,
+}
+
+impl ReplaceOptions {
+    /// Whether adding an account called `name` may replace an existing one.
+    /// Asks before doing so, unless `--yes` was given.
+    fn allows_replacing(&self, name: &str, backend: &dyn StorageBackend) -> Result<bool, AppError> {
+        if !self.force || self.yes {
+            return Ok(self.force);
+        }
+        let storage = get_storage(backend)?;
+        let Some(existing) = storage
+            .accounts
+            .iter()
+            .find(|account| same_name(&account.name, name.trim()))
+        else {
+            return Ok(true);
+        };
+        if !io::stdin().is_terminal() {
+            return Err(AppError::InvalidInput(format!(
+                "Not replacing '{}' without confirmation; pass --yes to replace it non-interactively",
+                existing.name
+            )));
+        }
+        if !confirm(&format!("Replace existing account '{}'?", existing.name))? {
+            return Err(AppError::new("Add cancelled"));
+        }
+        Ok(true)
+    }
+}
+
+/// Parses an RFC 3339 date into Unix seconds for `Account::epoch`.
+fn parse_epoch_date(value: &str) -> Result<u64, String> {
+    let date = OffsetDateTime::parse(value, &Rfc3339)
+        .map_err(|e| format!("expected a date such as 2024-01-01T00:00:00Z ({})", e))?;
+    if date > OffsetDateTime::now_utc() {
+        return Err("the epoch can't be in the future".to_string());
+    }
+    u64::try_from(date.unix_timestamp())
+        .map_err(|_| "the epoch can't be before 1970-01-01".to_string())
+}
+
+/// Shows an epoch as an RFC 3339 date, falling back to raw seconds if it is
+/// out of range.
+fn format_epoch(epoch: u64) -> String {
+    i64::try_from(epoch)
+        .ok()
+        .and_then(|secs| OffsetDateTime::from_unix_timestamp(secs).ok())
+        .and_then(|date| date.format(&Rfc3339).ok())
+        .unwrap_or_else(|| epoch.to_string())
+}
+
+/// How imports handle accounts whose name is already taken
+#[derive(Args)]
+#[group(multiple = false)]
+struct ConflictOptions {
+    /// Keep the existing account (the default)
+    #[arg(long)]
+    skip_existing: bool,
+    /// Replace the existing account with the imported one
+    #[arg(long)]
+    overwrite: bool,
+    /// Fail without importing anything
+    #[arg(long)]
+    error: bool,
+}
+
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+enum OnConflict {
+    #[default]
+    Skip,
+    Overwrite,
+    Error,
+}
+
+impl ConflictOptions {
+    fn on_conflict(&self) -> OnConflict {
+        if self.overwrite {
+            OnConflict::Overwrite
+        } else if self.error {
+            OnConflict::Error
+        } else {
+            OnConflict::Skip
+        }
+    }
+}
+
+/// Accepts any spelling `normalize_algorithm` understands, e.g. `sha-256`.
+fn parse_hash_algorithm(value: &str) -> Result<String, String> {
+    match normalize_algorithm(value) {
+        Some(algorithm) if algorithm != STEAM_ALGORITHM => Ok(algorithm.to_string()),
+        _ => Err("expected SHA1, SHA256 or SHA512".to_string()),
+    }
+}
+
+impl AccountOptions {
+    /// Builds the account described by these options, using
+    /// `default_algorithm` when no algorithm was given.
+    fn build(
+        &self,
+        name: &str,
+        secret: &str,
+        default_algorithm: Option<&str>,
+    ) -> Result<Account, AppError> {
+        let mut builder = Account::builder(name, secret);
+        if let Some(algorithm) = self.algorithm.as_deref().or(default_algorithm) {
+            builder = builder.algorithm(algorithm);
+        }
+        if let Some(digits) = self.digits {
+            builder = builder.digits(digits);
+        }
+        if let Some(epoch) = self.epoch_date {
+            builder = builder.epoch(epoch);
+        }
+        if let Some(period) = self.period {
+            builder = builder.period(period);
+        }
+        if let Some(issuer) = &self.issuer {
+            builder = builder.issuer(issuer);
+        }
+        if let Some(note) = &self.note {
+            builder = builder.note(note);
+        }
+        if let Some(encoding) = self.encoding {
+            builder = builder.encoding(encoding);
+        }
+        builder.build()
+    }
+
+    /// Warns when `account`'s secret is implausibly short, and asks before
+    /// adding it unless `--allow-short` was given.
+    fn check_secret_length(&self, account: &Account) -> Result<(), AppError> {
+        if self.allow_short || confirm_secret_length(account)? {
+            return Ok(());
+        }
+        Err(AppError::InvalidInput(
+            "Secret is too short; pass --allow-short to add it anyway".to_string(),
+        ))
+    }
+}
+
+/// Warns when `account`'s secret is implausibly short, and returns whether
+/// it should be added anyway. Only a user at a terminal can say yes.
+fn confirm_secret_length(account: &Account) -> Result<bool, AppError> {
+    let length = decode_secret(&account.secret, account.encoding)?.len();
+    if length >= MIN_SECRET_BYTES {
+        return Ok(true);
+    }
+    eprintln!(
+        "Warning: the secret for '{}' is only {} bytes; it may have been cut off when copied",
+        account.name, length
+    );
+    Ok(io::stdin().is_terminal() && confirm("Add it anyway?")?)
+}
+
+/// The settings `update` changes; anything left out is kept as it is
+#[derive(Args)]
+#[group(required = true, multiple = true)]
+struct UpdateOptions {
+    /// New secret, e.g. after the service reset it, in the account's encoding
+    #[arg(long)]
+    secret: Option<String>,
+    /// Hash algorithm used to generate codes: SHA1, SHA256 or SHA512
+    #[arg(long, value_parser = parse_hash_algorithm)]
+    algorithm: Option<String>,
+    /// Number of digits in each code
+    #[arg(long, value_parser = clap::value_parser!(u32).range(i64::from(MIN_DIGITS)..=i64::from(MAX_DIGITS)))]
+    digits: Option<u32>,
+    /// Seconds each code is valid for
+    #[arg(long, value_parser = clap::value_parser!(u32).range(1..))]
+    period: Option<u32>,
+    /// Service that issued the secret
+    #[arg(long)]
+    issuer: Option<String>,
+}
+
+impl UpdateOptions {
+    /// Applies the given settings to `account`. Validation happens in
+    /// `update_account`.
+    fn apply(&self, account: &mut Account) {
+        if let Some(secret) = &self.secret {
+            account.secret = secret.as_str().into();
+        }
+        if let Some(algorithm) = &self.algorithm {
+            account.algorithm = algorithm.clone();
+        }
+        if let Some(digits) = self.digits {
+            account.digits = digits;
+        }
+        if let Some(period) = self.period {
+            account.period = period;
+        }
+        if let Some(issuer) = &self.issuer {
+            account.issuer = issuer.clone();
+        }
+    }
+}
+
+/// Import sources that the --json path options don't apply to. clap doesn't
+/// enforce `requires = "json"` alongside another source, so these conflict.
+const NON_JSON_SOURCES: [&str; 5] = ["migration", "aegis", "andotp", "freeotp", "csv"];
+
 #[derive(Subcommand)]
 enum Commands {
     /// Add a new account with secret
+    #[command(after_help = EXIT_CODES_HELP)]
     Add {
-        /// Account name (e.g., email or service identifier). Optional when using --image (will use name from QR code or prompt)
+        /// Account name (e.g., email or service identifier). Optional when using --image, --uri or --from-clipboard (will use name from the URI or prompt)
         name: Option<String>,
         /// Load account from QR code image instead of prompting for secret
         #[arg(long, value_name = "IMAGE_PATH")]
         image: Option<String>,
+        /// Add the account described by an otpauth:// URI
+        #[arg(long, conflicts_with = "image")]
+        uri: Option<String>,
+        /// Read the QR code from an image on the clipboard, such as a
+        /// screenshot copied there
+        #[arg(long, conflicts_with_all = ["image", "uri"])]
+        from_clipboard: bool,
+        /// Base32 secret, instead of prompting for it
+        #[arg(long, conflicts_with_all = ["image", "uri", "from_clipboard", "secret_stdin"])]
+        secret: Option<String>,
+        /// Read the Base32 secret from the first line of stdin
+        #[arg(long, conflicts_with_all = ["image", "uri", "from_clipboard"])]
+        secret_stdin: bool,
+        #[command(flatten)]
+        options: AccountOptions,
+        #[command(flatten)]
+        replace: ReplaceOptions,
     },
     /// Generate code for an account
+    #[command(after_help = EXIT_CODES_HELP)]
     Code {
         /// Account name to generate code for
+        #[arg(required_unless_present = "all")]
+        name: Option<String>,
+        /// Show the current code for every account
+        #[arg(short, long, conflicts_with_all = ["name", "copy", "watch"])]
+        all: bool,
+        /// Print the code as a JSON object
+        #[arg(long)]
+        json: bool,
+        /// Omit the remaining validity from the output
+        #[arg(short, long)]
+        quiet: bool,
+        /// Copy the code to the system clipboard
+        #[arg(short, long, conflicts_with = "json")]
+        copy: bool,
+        /// Keep showing the code, refreshing it as it changes, until Ctrl-C
+        #[arg(short, long, conflicts_with_all = ["json", "copy"])]
+        watch: bool,
+        /// Also show the codes for N time steps either side of now, e.g. to
+        /// check for clock drift
+        #[arg(long, value_name = "N", default_value_t = 0, value_parser = clap::value_parser!(u32).range(..=i64::from(MAX_WINDOW)), conflicts_with_all = ["all", "json", "copy", "watch"])]
+        window: u32,
+    },
+    /// Find accounts by fuzzy-matching their names, best match first
+    #[command(after_help = EXIT_CODES_HELP)]
+    Search {
+        /// Text to match against account names
+        query: String,
+        /// Print the matches as a JSON array
+        #[arg(long)]
+        json: bool,
+        /// Show at most this many matches
+        #[arg(short = 'n', long, value_name = "N")]
+        limit: Option<usize>,
+        /// Copy the best match's code to the clipboard instead of listing matches
+        #[arg(long, conflicts_with_all = ["json", "limit"])]
+        copy_top: bool,
+    },
+    /// Show an account's details; --reveal adds the start of its secret
+    #[command(after_help = EXIT_CODES_HELP)]
+    Info {
+        /// Account name to show
+        name: String,
+        /// Print the details as a JSON object
+        #[arg(long)]
+        json: bool,
+        /// Include the first few characters of the secret, to tell secrets apart
+        #[arg(long)]
+        reveal: bool,
+    },
+    /// Check whether a code is currently valid for an account
+    #[command(after_help = EXIT_CODES_HELP)]
+    Verify {
+        /// Account name to verify against
         name: String,
+        /// Code to check
+        code: String,
+        /// Number of time steps either side of now to accept
+        #[arg(long, default_value_t = 1, value_parser = clap::value_parser!(u32).range(..=i64::from(MAX_WINDOW)))]
+        window: u32,
     },
     /// Delete an account
+    #[command(after_help = EXIT_CODES_HELP)]
     Delete {
         /// Account name to delete
         name: String,
+        /// Delete without asking for confirmation
+        #[arg(short = 'y', long)]
+        force: bool,
+    },
+    /// Rename an account
+    #[command(after_help = EXIT_CODES_HELP)]
+    Rename {
+        /// Current account name
+        old: String,
+        /// New account name
+        new: String,
+    },
+    /// Correct for a system clock that is off, for one account only
+    #[command(after_help = EXIT_CODES_HELP)]
+    SetOffset {
+        /// Account name to correct
+        name: String,
+        /// Seconds to add to the system clock; negative if it is fast, 0 to
+        /// stop correcting
+        #[arg(allow_negative_numbers = true)]
+        seconds: i64,
+    },
+    /// Change an account's secret or code settings, keeping everything else
+    #[command(after_help = EXIT_CODES_HELP)]
+    Update {
+        /// Account name to update
+        name: String,
+        #[command(flatten)]
+        options: UpdateOptions,
+    },
+    /// List all account names
+    #[command(after_help = EXIT_CODES_HELP)]
+    List {
+        /// Include the issuer alongside each account name
+        #[arg(long)]
+        show_issuer: bool,
+        /// Print accounts as a JSON array
+        #[arg(long)]
+        json: bool,
+    },
+    /// Import accounts from another authenticator
+    #[command(group(ArgGroup::new("source").required(true)), after_help = EXIT_CODES_HELP)]
+    Import {
+        /// Google Authenticator export URI (otpauth-migration://offline?data=...)
+        #[arg(long, value_name = "URI", group = "source")]
+        migration: Option<String>,
+        /// Unencrypted Aegis Authenticator JSON backup
+        #[arg(long, value_name = "FILE", group = "source")]
+        aegis: Option<String>,
+        /// Plain-text andOTP JSON backup
+        #[arg(long, value_name = "FILE", group = "source")]
+        andotp: Option<String>,
+        /// File of otpauth:// URIs, one per line, such as a FreeOTP export
+        #[arg(long, value_name = "FILE", group = "source")]
+        freeotp: Option<String>,
+        /// CSV with a header row and name and secret columns, plus optional
+        /// issuer, algorithm, digits and period columns
+        #[arg(long, value_name = "FILE", group = "source")]
+        csv: Option<String>,
+        /// JSON export, such as a password manager's, holding an otpauth://
+        /// URI in each item
+        #[arg(long, value_name = "FILE", group = "source")]
+        json: Option<String>,
+        /// Dotted path to the array of items in the JSON, such as `items`;
+        /// `*` stands for every element of an array. Defaults to the top level
+        #[arg(long, value_name = "PATH", conflicts_with_all = NON_JSON_SOURCES)]
+        items_path: Option<String>,
+        /// Dotted path to the otpauth:// URI within each item, such as
+        /// `login.totp`
+        #[arg(
+            long,
+            value_name = "PATH",
+            conflicts_with_all = NON_JSON_SOURCES,
+            required_unless_present_any = NON_JSON_SOURCES
+        )]
+        totp_path: Option<String>,
+        /// Dotted path to the account name within each item, such as `name`.
+        /// Defaults to the name in the URI
+        #[arg(long, value_name = "PATH", conflicts_with_all = NON_JSON_SOURCES)]
+        name_path: Option<String>,
+        #[command(flatten)]
+        conflicts: ConflictOptions,
+    },
+    /// Copy every account from the current storage backend to another one
+    #[command(group(ArgGroup::new("target").required(true)), after_help = EXIT_CODES_HELP)]
+    Migrate {
+        /// Write the accounts to this file
+        #[arg(long, value_name = "PATH", group = "target")]
+        to_file: Option<String>,
+        /// Write the accounts to the system keyring
+        #[arg(long, group = "target")]
+        to_keyring: bool,
+        /// Store each account as its own keyring entry instead of keeping
+        /// them all in one. Later commands pick this layout up automatically
+        #[arg(long, group = "target")]
+        to_keyring_entries: bool,
+        /// Replace any accounts already stored in the target
+        #[arg(long)]
+        force: bool,
+    },
+    /// Require a passphrase before the dashboard shows any codes
+    #[command(after_help = EXIT_CODES_HELP)]
+    SetPassphrase {
+        /// Read passphrases from stdin, one per line, instead of prompting.
+        /// The current passphrase comes first if one is set
+        #[arg(long)]
+        passphrase_stdin: bool,
+    },
+    /// Stop asking for a passphrase when opening the dashboard
+    #[command(after_help = EXIT_CODES_HELP)]
+    ClearPassphrase {
+        /// Read the current passphrase from stdin instead of prompting
+        #[arg(long)]
+        passphrase_stdin: bool,
+    },
+    /// Print an account's Base32 secret, e.g. to move it to another app by hand
+    #[command(after_help = EXIT_CODES_HELP)]
+    Reveal {
+        /// Account name whose secret to print
+        name: String,
+        /// Skip the confirmation prompt
+        #[arg(short = 'y', long)]
+        yes: bool,
+        /// Read the passphrase, if one is set, from stdin instead of prompting
+        #[arg(long)]
+        passphrase_stdin: bool,
+    },
+    /// Save the whole store to a JSON file
+    #[command(after_help = EXIT_CODES_HELP)]
+    Backup {
+        /// File to write the backup to
+        #[arg(short, long, value_name = "PATH")]
+        output: String,
+    },
+    /// Load accounts from a file written by `backup`
+    #[command(group(ArgGroup::new("restore_mode").required(true)), after_help = EXIT_CODES_HELP)]
+    Restore {
+        /// Backup file to read
+        #[arg(short, long, value_name = "PATH")]
+        input: String,
+        /// Add accounts from the backup, skipping names that already exist
+        #[arg(long, group = "restore_mode")]
+        merge: bool,
+        /// Replace the whole store with the backup
+        #[arg(long, group = "restore_mode")]
+        replace: bool,
+        #[command(flatten)]
+        conflicts: ConflictOptions,
+    },
+    /// Export all accounts
+    #[command(group(ArgGroup::new("export_format").required(true)), after_help = EXIT_CODES_HELP)]
+    Export {
+        /// Print every account as an otpauth URI, one per line
+        #[arg(long, group = "export_format")]
+        uris: bool,
+        /// Write each account's name, issuer, algorithm, digits and period
+        /// to a CSV file, or to stdout if PATH is '-'
+        #[arg(
+            long,
+            value_name = "PATH",
+            group = "export_format",
+            conflicts_with = "output"
+        )]
+        csv: Option<String>,
+        /// Add each account's secret to the CSV
+        #[arg(long, requires = "csv")]
+        include_secrets: bool,
+        /// Write the export to a file instead of stdout
+        #[arg(short, long, value_name = "PATH")]
+        output: Option<String>,
+        /// Skip the confirmation prompt
+        #[arg(long)]
+        yes_i_understand_this_exposes_secrets: bool,
+    },
+    /// Manage profiles
+    Profile {
+        #[command(subcommand)]
+        command: ProfileCommands,
+    },
+    /// Compare the system clock with an NTP server, since codes are only
+    /// accepted while the clock is right
+    #[command(after_help = EXIT_CODES_HELP)]
+    CheckTime {
+        /// NTP server to ask, optionally with a port
+        #[arg(long, default_value = ntp::DEFAULT_SERVER)]
+        server: String,
+    },
+    /// Print a shell completion script to stdout
+    Completions {
+        /// Shell to generate completions for
+        shell: Shell,
     },
     /// Export account as QR code
-    #[command(arg_required_else_help = true)]
+    #[command(arg_required_else_help = true, after_help = EXIT_CODES_HELP)]
     ExportQr {
         /// Account name to export
-        #[arg(long)]
-        name: String,
+        #[arg(long, required_unless_present = "all")]
+        name: Option<String>,
+        /// Write a PNG for every account into --output-dir
+        #[arg(long, conflicts_with_all = ["name", "format", "output"])]
+        all: bool,
+        /// With --all, put every account in Google Authenticator "Transfer
+        /// accounts" QR codes, shown in turn or written to --output-dir
+        // `requires = "all"` would be met by the flag's default value
+        #[arg(long, conflicts_with = "name")]
+        migration: bool,
+        /// Directory to write the PNGs to when using --all
+        #[arg(long, value_name = "DIR", requires = "all", required_unless_present_any = ["name", "migration"])]
+        output_dir: Option<String>,
+        /// Skip the confirmation prompt when using --all
+        #[arg(long, requires = "all")]
+        yes_i_understand_this_exposes_secrets: bool,
+        /// Output format (inferred from the --output extension when omitted)
+        #[arg(long, value_enum)]
+        format: Option<QrFormat>,
+        /// Write the QR code to a file instead of the terminal
+        #[arg(short, long, value_name = "PATH")]
+        output: Option<String>,
+        /// Size in pixels of each QR module when writing an image
+        #[arg(long, default_value_t = 8, value_parser = clap::value_parser!(u32).range(1..))]
+        module_size: u32,
+        /// SVG foreground color (hex such as #000000 or a color name)
+        #[arg(long, default_value = "#000000")]
+        foreground: String,
+        /// SVG background color (hex such as #ffffff or a color name)
+        #[arg(long, default_value = "#ffffff")]
+        background: String,
+        /// Error correction level; higher levels make codes bigger but easier
+        /// to scan from glossy or small screens
+        #[arg(long, value_enum, ignore_case = true, default_value_t = QrEcc::M)]
+        ecc: QrEcc,
+        /// Width in modules of the light border scanners need around the code
+        #[arg(long, value_name = "N", default_value_t = 4, value_parser = clap::value_parser!(u32).range(..=64))]
+        quiet_zone: u32,
+        /// Also print the otpauth URI, which contains the secret in plain text
+        #[arg(long, conflicts_with = "all")]
+        show_uri: bool,
     },
 }
 
-#[derive(Serialize, Deserialize, Default, Clone)]
-struct Storage {
+/// How much of a QR code can be damaged and still be read
+#[derive(Clone, Copy, PartialEq, ValueEnum)]
+enum QrEcc {
+    /// About 7%
+    #[value(name = "L")]
+    L,
+    /// About 15%
+    #[value(name = "M")]
+    M,
+    /// About 25%
+    #[value(name = "Q")]
+    Q,
+    /// About 30%
+    #[value(name = "H")]
+    H,
+}
+
+#[derive(Clone, Copy, PartialEq, ValueEnum)]
+enum QrFormat {
+    /// Unicode blocks printed to the terminal
+    Terminal,
+    Png,
+    Svg,
+}
+
+/// Trims surrounding whitespace from an account name, so "github " and
+/// "github" can't become two accounts, and rejects names that are left empty.
+fn normalize_account_name(name: &str) -> Result<String, AppError> {
+    let name = name.trim();
+    if name.is_empty() {
+        return Err(AppError::InvalidInput(
+            "Account name cannot be empty".to_string(),
+        ));
+    }
+    Ok(name.to_string())
+}
+
+/// Account names are compared case-insensitively, so "GitHub" and "github"
+/// are the same account. The casing it was added with is kept for display.
+fn same_name(a: &str, b: &str) -> bool {
+    a == b || a.to_lowercase() == b.to_lowercase()
+}
+
+/// Finds the account called `name`. An exact match wins; otherwise the name
+/// is matched ignoring case, which is ambiguous only for stores that were
+/// written before names were case-insensitive.
+fn find_account_index(accounts: &[Account], name: &str) -> Result<usize, AppError> {
+    if let Some(index) = accounts.iter().position(|a| a.name == name) {
+        return Ok(index);
+    }
+    let mut matches = accounts
+        .iter()
+        .enumerate()
+        .filter(|(_, a)| same_name(&a.name, name));
+    match (matches.next(), matches.next()) {
+        (Some((index, _)), None) => Ok(index),
+        (Some(_), Some(_)) => Err(AppError::InvalidInput(format!(
+            "Account name '{}' is ambiguous; use its exact casing",
+            name
+        ))),
+        (None, _) => Err(AppError::NotFound(name.to_string())),
+    }
+}
+
+/// Adds `account`, returning whether it replaced an existing account with
+/// the same name, which is only allowed with `replace`.
+fn save_account(
+    mut account: Account,
+    replace: bool,
+    backend: &dyn StorageBackend,
+) -> Result<bool, AppError> {
+    account.name = normalize_account_name(&account.name)?;
+    account.secret = validate_secret(&account.secret, account.encoding)?.into();
+    account.validate()?;
+    update_storage(backend, |storage| {
+        let existing = storage
+            .accounts
+            .iter()
+            .position(|a| same_name(&a.name, &account.name));
+        if let Some(index) = existing {
+            if !replace {
+                return Err(AppError::InvalidInput(format!(
+                    "Account '{}' already exists",
+                    storage.accounts[index].name
+                )));
+            }
+            storage.accounts.remove(index);
+        }
+        storage.accounts.push(account);
+        storage.accounts.sort_by(|a, b| a.name.cmp(&b.name));
+        Ok(existing.is_some())
+    })
+}
+
+fn added_or_replaced(replaced: bool) -> &'static str {
+    if replaced { "Replaced" } else { "Added" }
+}
+
+/// Adds several accounts at once, resolving names that already exist
+/// according to `on_conflict`. `rejected` describes entries the importer
+/// could not convert, which are reported alongside the ones skipped here.
+fn import_accounts(
     accounts: Vec<Account>,
+    rejected: Vec<String>,
+    on_conflict: OnConflict,
+    backend: &dyn StorageBackend,
+) -> Result<(), AppError> {
+    for reason in &rejected {
+        println!("Skipped {}", reason);
+    }
+    let (imported, overwritten, skipped) = update_storage(backend, |storage| {
+        let mut imported = 0;
+        let mut overwritten = 0;
+        let mut skipped = rejected.len();
+
+        for mut account in accounts {
+            match normalize_account_name(&account.name).and_then(|name| {
+                account.name = name;
+                account.secret = validate_secret(&account.secret, account.encoding)?.into();
+                account.validate()
+            }) {
+                Ok(()) => {}
+                Err(e) => {
+                    println!("Skipped '{}': {}", account.name, e);
+                    skipped += 1;
+                    continue;
+                }
+            }
+
+            let Some(existing) = storage
+                .accounts
+                .iter_mut()
+                .find(|a| same_name(&a.name, &account.name))
+            else {
+                storage.accounts.push(account);
+                imported += 1;
+                continue;
+            };
+            let differs = if existing.secret == account.secret {
+                ""
+            } else {
+                " with a different secret"
+            };
+            match on_conflict {
+                OnConflict::Skip => {
+                    println!(
+                        "Skipped '{}': account already exists{}",
+                        account.name, differs
+                    );
+                    skipped += 1;
+                }
+                OnConflict::Overwrite => {
+                    println!("Overwrote '{}'", account.name);
+                    *existing = account;
+                    overwritten += 1;
+                }
+                OnConflict::Error => {
+                    return Err(AppError::InvalidInput(format!(
+                        "Account '{}' already exists{}; nothing was imported",
+                        account.name, differs
+                    )));
+                }
+            }
+        }
+
+        storage.accounts.sort_by(|a, b| a.name.cmp(&b.name));
+        Ok((imported, overwritten, skipped))
+    })?;
+    if on_conflict == OnConflict::Overwrite {
+        println!(
+            "Imported {} account(s), overwrote {}, skipped {}",
+            imported, overwritten, skipped
+        );
+    } else {
+        println!("Imported {} account(s), skipped {}", imported, skipped);
+    }
+    Ok(())
 }
 
-fn get_storage(file_path: Option<&str>) -> Result<Storage, AppError> {
-    if let Some(path) = file_path {
-        // File-backed storage
-        if Path::new(path).exists() {
-            let data = fs::read_to_string(path)
-                .map_err(|e| AppError::new(format!("Failed to read file {}: {}", path, e)))?;
-            Ok(serde_json::from_str(&data)?)
-        } else {
-            Ok(Storage::default())
+fn import_migration(
+    uri: &str,
+    on_conflict: OnConflict,
+    backend: &dyn StorageBackend,
+) -> Result<(), AppError> {
+    import_migration_batch(migration::parse_migration_uri(uri)?, on_conflict, backend)
+}
+
+fn import_migration_batch(
+    batch: migration::MigrationBatch,
+    on_conflict: OnConflict,
+    backend: &dyn StorageBackend,
+) -> Result<(), AppError> {
+    if batch.batch_size > 1 {
+        println!(
+            "Migration batch {} of {}",
+            batch.batch_index + 1,
+            batch.batch_size
+        );
+    }
+    import_accounts(batch.accounts, batch.skipped, on_conflict, backend)
+}
+
+fn import_aegis(
+    backup_path: &str,
+    on_conflict: OnConflict,
+    backend: &dyn StorageBackend,
+) -> Result<(), AppError> {
+    let json = fs::read_to_string(backup_path)
+        .map_err(|e| AppError::Storage(format!("Failed to read file {}: {}", backup_path, e)))?;
+    let (accounts, skipped) = aegis::parse_aegis_backup(&json)?;
+    import_accounts(accounts, skipped, on_conflict, backend)
+}
+
+fn import_andotp(
+    backup_path: &str,
+    on_conflict: OnConflict,
+    backend: &dyn StorageBackend,
+) -> Result<(), AppError> {
+    let contents = fs::read(backup_path)
+        .map_err(|e| AppError::Storage(format!("Failed to read file {}: {}", backup_path, e)))?;
+    let (accounts, skipped) = andotp::parse_andotp_backup(&contents)?;
+    import_accounts(accounts, skipped, on_conflict, backend)
+}
+
+fn import_json(
+    path: &str,
+    paths: &json_accounts::JsonPaths,
+    on_conflict: OnConflict,
+    backend: &dyn StorageBackend,
+) -> Result<(), AppError> {
+    let json = fs::read_to_string(path)
+        .map_err(|e| AppError::Storage(format!("Failed to read file {}: {}", path, e)))?;
+    let (accounts, skipped) = json_accounts::parse_accounts_json(&json, paths)?;
+    import_accounts(accounts, skipped, on_conflict, backend)
+}
+
+fn import_uri_list(
+    path: &str,
+    on_conflict: OnConflict,
+    backend: &dyn StorageBackend,
+) -> Result<(), AppError> {
+    let data = fs::read_to_string(path)
+        .map_err(|e| AppError::Storage(format!("Failed to read file {}: {}", path, e)))?;
+    let (accounts, skipped) = parse_uri_list(&data);
+    import_accounts(accounts, skipped, on_conflict, backend)
+}
+
+/// Parses one otpauth:// URI per line, ignoring blank lines. Lines that
+/// don't parse are described by line number so a mangled export can be fixed.
+fn parse_uri_list(data: &str) -> (Vec<Account>, Vec<String>) {
+    let mut accounts = Vec::new();
+    let mut skipped = Vec::new();
+    for (index, line) in data.lines().enumerate() {
+        let line = line.trim();
+        if line.is_empty() {
+            continue;
         }
+        warn_issuer_mismatch(line);
+        match Account::from_otpauth_uri(line) {
+            Ok(account) => accounts.push(account),
+            Err(e) => skipped.push(format!("line {}: {}", index + 1, e)),
+        }
+    }
+    (accounts, skipped)
+}
+
+/// Warns when otpauth URI `uri` names one issuer in its parameters and
+/// another in its label, which usually means it was put together wrongly.
+fn warn_issuer_mismatch(uri: &str) {
+    if let Some((param, label)) = Account::label_issuer_mismatch(uri) {
+        eprintln!(
+            "Warning: otpauth URI issuer '{}' does not match its label prefix '{}'; using '{}'",
+            param, label, param
+        );
+    }
+}
+
+fn import_csv(
+    path: &str,
+    on_conflict: OnConflict,
+    backend: &dyn StorageBackend,
+) -> Result<(), AppError> {
+    let data = fs::read_to_string(path)
+        .map(Zeroizing::new)
+        .map_err(|e| AppError::Storage(format!("Failed to read file {}: {}", path, e)))?;
+    let (accounts, skipped) = csv_accounts::parse_accounts_csv(&data)?;
+    import_accounts(accounts, skipped, on_conflict, backend)
+}
+
+/// The keyring store for the active profile: per-account entries once
+/// `migrate --to-keyring-entries` has run, otherwise the single entry.
+fn keyring_backend() -> Box<dyn StorageBackend> {
+    // A keyring that can't be read is reported when the store is first used
+    if KeyringEntriesBackend::exists().unwrap_or(false) {
+        Box::new(KeyringEntriesBackend::new())
     } else {
-        // Keyring storage
-        let entry = Entry::new(SERVICE_NAME, STORAGE_KEY).map_err(AppError::from)?;
+        Box::new(KeyringBackend)
+    }
+}
 
-        match entry.get_password() {
-            Ok(data) => Ok(serde_json::from_str(&data)?),
-            Err(keyring::Error::NoEntry) => Ok(Storage::default()),
-            Err(e) => Err(AppError::from(e)),
+/// Copies the store in `source` to `target`. The source is left unchanged.
+fn migrate_storage(
+    source: &dyn StorageBackend,
+    target: &dyn StorageBackend,
+    force: bool,
+) -> Result<(), AppError> {
+    if source.describe() == target.describe() {
+        return Err(AppError::InvalidInput(
+            "The source and target storage are the same".to_string(),
+        ));
+    }
+
+    let storage = get_storage(source)?;
+    let count = storage.accounts.len();
+    update_storage(target, |existing| {
+        if !existing.accounts.is_empty() && !force {
+            return Err(AppError::InvalidInput(format!(
+                "The target already holds {} account(s); pass --force to replace them",
+                existing.accounts.len()
+            )));
         }
+        *existing = storage;
+        Ok(())
+    })?;
+
+    println!("Migrated {} account(s) to {}", count, target.describe());
+    Ok(())
+}
+
+fn read_passphrase(prompt: &str, from_stdin: bool) -> Result<Zeroizing<String>, AppError> {
+    if from_stdin {
+        let mut input = Zeroizing::new(String::new());
+        io::stdin().read_line(&mut input)?;
+        return Ok(Zeroizing::new(
+            input.trim_end_matches(['\r', '\n']).to_string(),
+        ));
     }
+    Ok(Zeroizing::new(prompt_password(prompt)?))
 }
 
-fn save_storage(storage: &Storage, file_path: Option<&str>) -> Result<(), AppError> {
-    let data = serde_json::to_string_pretty(storage)?;
+/// Asks for the current passphrase, if one is set, before it can be changed
+/// or secrets can be shown.
+fn check_current_passphrase(storage: &Storage, from_stdin: bool) -> Result<(), AppError> {
+    if let Some(hash) = &storage.passphrase_hash {
+        let current = read_passphrase("Current passphrase: ", from_stdin)?;
+        if !passphrase::verify(&current, hash)? {
+            return Err(AppError::InvalidInput("Incorrect passphrase".to_string()));
+        }
+    }
+    Ok(())
+}
+
+fn set_passphrase(from_stdin: bool, backend: &dyn StorageBackend) -> Result<(), AppError> {
+    check_current_passphrase(&get_storage(backend)?, from_stdin)?;
+
+    let new = read_passphrase("New passphrase: ", from_stdin)?;
+    if new.is_empty() {
+        return Err(AppError::InvalidInput(
+            "The passphrase can't be empty".to_string(),
+        ));
+    }
+    if !from_stdin && read_passphrase("Confirm passphrase: ", false)? != new {
+        return Err(AppError::InvalidInput(
+            "Passphrases don't match".to_string(),
+        ));
+    }
 
-    if let Some(path) = file_path {
-        // File-backed storage
-        if let Some(parent) = Path::new(path).parent() {
-            fs::create_dir_all(parent)
-                .map_err(|e| AppError::new(format!("Failed to create directory: {}", e)))?;
+    let hash = passphrase::hash(&new)?;
+    update_storage(backend, |storage| {
+        storage.passphrase_hash = Some(hash);
+        Ok(())
+    })?;
+    println!("Passphrase set; the dashboard will ask for it before showing codes");
+    Ok(())
+}
+
+/// Prints an account's secret in Base32, after the passphrase if one is set
+/// and, unless `confirmed`, a warning the user has to accept.
+fn reveal_secret(
+    name: &str,
+    confirmed: bool,
+    passphrase_stdin: bool,
+    backend: &dyn StorageBackend,
+) -> Result<(), AppError> {
+    let storage = get_storage(backend)?;
+    let account = &storage.accounts[find_account_index(&storage.accounts, name)?];
+    check_current_passphrase(&storage, passphrase_stdin)?;
+
+    let prompt = format!(
+        "This prints the secret for '{}' in plaintext; anyone who sees it can generate your codes. Continue?",
+        account.name
+    );
+    if !confirmed && !confirm(&prompt)? {
+        return Err(AppError::new("Reveal cancelled"));
+    }
+    println!("{}", account.base32_secret().as_str());
+    Ok(())
+}
+
+fn clear_passphrase(from_stdin: bool, backend: &dyn StorageBackend) -> Result<(), AppError> {
+    let storage = get_storage(backend)?;
+    if storage.passphrase_hash.is_none() {
+        return Err(AppError::new("No passphrase is set"));
+    }
+    check_current_passphrase(&storage, from_stdin)?;
+
+    update_storage(backend, |storage| {
+        storage.passphrase_hash = None;
+        Ok(())
+    })?;
+    println!("Passphrase cleared");
+    Ok(())
+}
+
+fn backup_storage(output: &str, backend: &dyn StorageBackend) -> Result<(), AppError> {
+    let storage = get_storage(backend)?;
+    let data = serde_json::to_string_pretty(&storage)?;
+    write_file_atomically(Path::new(output), |file| file.write_all(data.as_bytes()))
+        .map_err(|e| AppError::Storage(format!("Failed to write file {}: {}", output, e)))?;
+    println!(
+        "Backed up {} account(s) to {}",
+        storage.accounts.len(),
+        output
+    );
+    Ok(())
+}
+
+fn restore_storage(
+    input: &str,
+    replace: bool,
+    on_conflict: OnConflict,
+    backend: &dyn StorageBackend,
+) -> Result<(), AppError> {
+    let data = fs::read_to_string(input)
+        .map_err(|e| AppError::Storage(format!("Failed to read file {}: {}", input, e)))?;
+    let mut backup: Storage = serde_json::from_str(&data)?;
+
+    if !replace {
+        return import_accounts(backup.accounts, Vec::new(), on_conflict, backend);
+    }
+
+    for account in &mut backup.accounts {
+        account.secret = validate_secret(&account.secret, account.encoding)
+            .and_then(|secret| {
+                account.validate()?;
+                Ok(secret.into())
+            })
+            .map_err(|e| {
+                AppError::InvalidInput(format!("Account '{}' in backup: {}", account.name, e))
+            })?;
+    }
+    let count = backup.accounts.len();
+    update_storage(backend, |storage| {
+        *storage = backup;
+        Ok(())
+    })?;
+    println!("Restored {} account(s) from {}", count, input);
+    Ok(())
+}
+
+fn get_account(name: &str, backend: &dyn StorageBackend) -> Result<Account, AppError> {
+    let mut storage = get_storage(backend)?;
+    let index = find_account_index(&storage.accounts, name)?;
+    Ok(storage.accounts.swap_remove(index))
+}
+
+/// Asks before deleting `name` unless `force` is set. Without a terminal to
+/// ask on, `force` is required so scripts can't delete an account by accident.
+fn confirm_delete(name: &str, force: bool, backend: &dyn StorageBackend) -> Result<(), AppError> {
+    if force {
+        return Ok(());
+    }
+    let account = get_account(name, backend)?;
+    if !io::stdin().is_terminal() {
+        return Err(AppError::InvalidInput(format!(
+            "Not deleting '{}' without confirmation; pass --force to delete it non-interactively",
+            account.name
+        )));
+    }
+    if !confirm(&format!("Delete account '{}'?", account.name))? {
+        return Err(AppError::new("Delete cancelled"));
+    }
+    Ok(())
+}
+
+fn delete_account(name: &str, backend: &dyn StorageBackend) -> Result<(), AppError> {
+    delete_accounts(&[name], backend)
+}
+
+/// Removes every account in `names` in a single write, or none of them if
+/// any is missing.
+fn delete_accounts(names: &[&str], backend: &dyn StorageBackend) -> Result<(), AppError> {
+    update_storage(backend, |storage| {
+        for name in names {
+            let index = find_account_index(&storage.accounts, name)?;
+            storage.accounts.remove(index);
         }
-        fs::write(path, data)
-            .map_err(|e| AppError::new(format!("Failed to write file {}: {}", path, e)))
+        Ok(())
+    })
+}
+
+/// Replaces the stored account that has the same name as `account`.
+fn update_account(mut account: Account, backend: &dyn StorageBackend) -> Result<(), AppError> {
+    account.secret = validate_secret(&account.secret, account.encoding)?.into();
+    account.validate()?;
+    update_storage(backend, |storage| {
+        let index = find_account_index(&storage.accounts, &account.name)?;
+        storage.accounts[index] = account;
+        Ok(())
+    })
+}
+
+#[derive(Serialize)]
+struct CodeOutput<'a> {
+    name: &'a str,
+    code: String,
+    remaining_seconds: u64,
+    period: u32,
+}
+
+fn show_code(
+    name: &str,
+    backend: &dyn StorageBackend,
+    json: bool,
+    quiet: bool,
+    copy: bool,
+    group: bool,
+) -> Result<(), AppError> {
+    let account = get_account(name, backend)?;
+    let duration = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .expect("System time is before Unix epoch");
+    let totp = generate_totp_full(&account, duration)?;
+    let remaining_seconds = totp.remaining_seconds(duration);
+
+    if copy {
+        // Headless and some Wayland sessions have no usable clipboard; fall
+        // back to printing the code rather than failing
+        match copy_to_clipboard(&totp.formatted) {
+            Ok(()) => {
+                println!(
+                    "Copied code for {} to clipboard (expires in {}s)",
+                    name, remaining_seconds
+                );
+                return Ok(());
+            }
+            Err(e) => eprintln!("Warning: could not access clipboard: {}", e),
+        }
+    }
+
+    if json {
+        let output = CodeOutput {
+            name,
+            code: totp.formatted,
+            remaining_seconds,
+            period: account.period,
+        };
+        println!("{}", serde_json::to_string(&output)?);
+    } else if quiet {
+        println!("Code for {}: {}", name, totp.display(group));
     } else {
-        // Keyring storage
-        Entry::new(SERVICE_NAME, STORAGE_KEY)?
-            .set_password(&data)
-            .map_err(AppError::from)
+        println!(
+            "Code for {}: {} (expires in {}s)",
+            name,
+            totp.display(group),
+            remaining_seconds
+        );
     }
+    Ok(())
 }
 
-fn save_account(name: &str, secret: &str, file_path: Option<&str>) -> Result<(), AppError> {
-    let mut storage = get_storage(file_path)?;
-    if storage.accounts.iter().any(|a| a.name == name) {
-        return Err(AppError::new(format!("Account '{}' already exists", name)));
+/// Prints the codes for the time steps from `window` before the current one
+/// to `window` after it, each with its offset and when it is valid.
+fn show_code_window(account: &Account, window: u32, group: bool) -> Result<(), AppError> {
+    let now = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .expect("System time is before Unix epoch");
+    println!("Codes for {}:", account.name);
+    write_code_window(&mut io::stdout().lock(), account, now, window, group)
+}
+
+fn write_code_window(
+    out: &mut impl Write,
+    account: &Account,
+    now: Duration,
+    window: u32,
+    group: bool,
+) -> Result<(), AppError> {
+    let key = decode_secret(&account.secret, account.encoding)?;
+    let current = account.time_step(now);
+    let window = i64::from(window);
+    for offset in -window..=window {
+        // Steps before the epoch don't exist
+        let Some(step) = current.checked_add_signed(offset) else {
+            continue;
+        };
+        let totp = generate_totp_for_step(account, &key, step)?;
+        let (label, marker) = if offset == 0 {
+            ("0".to_string(), " (current)")
+        } else {
+            (format!("{:+}", offset), "")
+        };
+        writeln!(
+            out,
+            "{:>4}  {}  {}-{} UTC{}",
+            label,
+            totp.display(group),
+            format_time_of_day(account.step_start(step)),
+            format_time_of_day(totp.valid_until.as_secs()),
+            marker
+        )?;
     }
-    storage
-        .accounts
-        .push(Account::new(name.to_string(), secret.to_string()));
-    storage.accounts.sort_by(|a, b| a.name.cmp(&b.name));
-    save_storage(&storage, file_path)
+    Ok(())
 }
 
-fn get_account(name: &str, file_path: Option<&str>) -> Result<Account, AppError> {
-    let storage = get_storage(file_path)?;
-    storage
-        .accounts
-        .iter()
-        .find(|a| a.name == name)
-        .cloned()
-        .ok_or_else(|| AppError::new(format!("Account '{}' not found", name)))
+/// Shows Unix time `secs` as a UTC time of day, e.g. `09:41:30`.
+fn format_time_of_day(secs: u64) -> String {
+    let secs = secs % 86_400;
+    format!(
+        "{:02}:{:02}:{:02}",
+        secs / 3600,
+        secs % 3600 / 60,
+        secs % 60
+    )
 }
 
-fn delete_account(name: &str, file_path: Option<&str>) -> Result<(), AppError> {
-    let mut storage = get_storage(file_path)?;
-    let initial_len = storage.accounts.len();
-    storage.accounts.retain(|a| a.name != name);
-    if storage.accounts.len() == initial_len {
-        return Err(AppError::new(format!("Account '{}' not found", name)));
-    }
-    save_storage(&storage, file_path)
+#[cfg(feature = "clipboard")]
+fn copy_to_clipboard(text: &str) -> Result<(), String> {
+    Clipboard::new()
+        .and_then(|mut clipboard| clipboard.set_text(text))
+        .map_err(|e| e.to_string())
 }
 
-fn handle_error(err: AppError) {
-    eprintln!("Error: {}", err);
-    if let Some(source) = err.source() {
-        eprintln!("Caused by: {}", source);
+#[cfg(not(feature = "clipboard"))]
+fn copy_to_clipboard(_text: &str) -> Result<(), String> {
+    Err("this build of hotpot has no clipboard support".to_string())
+}
+
+/// Prints the current code for every account, sorted by name. Each account's
+/// own period decides how long its code has left.
+fn show_all_codes(
+    backend: &dyn StorageBackend,
+    json: bool,
+    quiet: bool,
+    group: bool,
+) -> Result<(), AppError> {
+    let mut storage = get_storage(backend)?;
+    if storage.accounts.is_empty() {
+        return Err(AppError::new("No accounts found"));
     }
+    storage.accounts.sort_by(|a, b| a.name.cmp(&b.name));
+    let duration = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .expect("System time is before Unix epoch");
+
+    let mut outputs = Vec::with_capacity(storage.accounts.len());
+    for account in &storage.accounts {
+        let totp = generate_totp_full(account, duration)?;
+        outputs.push(CodeOutput {
+            name: &account.name,
+            remaining_seconds: totp.remaining_seconds(duration),
+            code: if json {
+                totp.formatted
+            } else {
+                totp.display(group)
+            },
+            period: account.period,
+        });
+    }
+
+    if json {
+        println!("{}", serde_json::to_string_pretty(&outputs)?);
+        return Ok(());
+    }
+    for output in &outputs {
+        if quiet {
+            println!("{}: {}", output.name, output.code);
+        } else {
+            println!(
+                "{}: {} (expires in {}s)",
+                output.name, output.code, output.remaining_seconds
+            );
+        }
+    }
+    Ok(())
+}
+
+/// Redraws the account's code once a second on a single line until the user
+/// presses Ctrl-C, Esc or q.
+#[cfg(feature = "dashboard")]
+fn watch_code(account: &Account, group: bool) -> Result<(), AppError> {
+    let mut stdout = io::stdout();
+    enable_raw_mode()?;
+    queue!(stdout, Hide)?;
+
+    let result = (|| -> Result<(), AppError> {
+        loop {
+            let duration = SystemTime::now()
+                .duration_since(UNIX_EPOCH)
+                .expect("System time is before Unix epoch");
+            let totp = generate_totp_full(account, duration)?;
+            queue!(
+                stdout,
+                MoveToColumn(0),
+                Clear(ClearType::CurrentLine),
+                Print(format!(
+                    "Code for {}: {} (expires in {}s)",
+                    account.name,
+                    totp.display(group),
+                    totp.remaining_seconds(duration)
+                ))
+            )?;
+            stdout.flush()?;
+
+            // Wake up at the start of the next second so the countdown stays in step
+            let until_next_second =
+                Duration::from_nanos(1_000_000_000 - u64::from(duration.subsec_nanos()));
+            if poll(until_next_second)? {
+                if let Event::Key(key) = read()? {
+                    if key.code == KeyCode::Esc
+                        || key.code == KeyCode::Char('q')
+                        || (key.code == KeyCode::Char('c')
+                            && key.modifiers.contains(KeyModifiers::CONTROL))
+                    {
+                        return Ok(());
+                    }
+                }
+            }
+        }
+    })();
+
+    queue!(stdout, Show, Print("\r\n"))?;
+    stdout.flush()?;
+    disable_raw_mode()?;
+    result
+}
+
+#[cfg(not(feature = "dashboard"))]
+fn watch_code(_account: &Account, _group: bool) -> Result<(), AppError> {
+    Err(AppError::InvalidInput(
+        "This build of hotpot can't watch codes; rebuild it with the 'dashboard' feature"
+            .to_string(),
+    ))
 }
 
-fn export_qr_code(name: &str, secret: &str) -> Result<(), AppError> {
-    use qrcode::{QrCode, render::unicode};
+#[derive(Serialize)]
+struct InfoOutput<'a> {
+    name: &'a str,
+    issuer: &'a str,
+    algorithm: &'a str,
+    digits: u32,
+    period: u32,
+    epoch: String,
+    #[serde(skip_serializing_if = "is_zero")]
+    offset_seconds: i64,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    note: Option<&'a str>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    secret_preview: Option<String>,
+    code: String,
+    remaining_seconds: u64,
+}
+
+fn is_zero(value: &i64) -> bool {
+    *value == 0
+}
+
+/// The first few characters of a secret, enough to tell two apart without
+/// giving the secret away.
+fn secret_preview(secret: &str) -> String {
+    let preview: String = secret.chars().take(4).collect();
+    format!("{}…", preview)
+}
 
-    let uri = generate_otpauth_uri(name, secret);
-    println!("Generated URI: {}", uri);
-    let code =
-        QrCode::new(uri.as_bytes()).map_err(|e| AppError::new(format!("QR code error: {}", e)))?;
-    let qr_string = code
-        .render::<unicode::Dense1x2>()
-        .dark_color(unicode::Dense1x2::Light)
-        .light_color(unicode::Dense1x2::Dark)
-        .build();
+fn show_info(
+    name: &str,
+    backend: &dyn StorageBackend,
+    json: bool,
+    reveal: bool,
+) -> Result<(), AppError> {
+    let account = get_account(name, backend)?;
+    let duration = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .expect("System time is before Unix epoch");
+    let totp = generate_totp_full(&account, duration)?;
+    let output = InfoOutput {
+        name: &account.name,
+        issuer: &account.issuer,
+        algorithm: &account.algorithm,
+        digits: account.digits,
+        period: account.period,
+        epoch: format_epoch(account.epoch),
+        offset_seconds: account.offset_seconds,
+        note: account.note.as_deref(),
+        secret_preview: reveal.then(|| secret_preview(&account.secret)),
+        remaining_seconds: totp.remaining_seconds(duration),
+        code: totp.formatted,
+    };
 
-    println!("\n{}", qr_string);
+    if json {
+        println!("{}", serde_json::to_string(&output)?);
+        return Ok(());
+    }
+    println!("Name:      {}", output.name);
+    println!("Issuer:    {}", output.issuer);
+    println!("Algorithm: {}", output.algorithm);
+    println!("Digits:    {}", output.digits);
+    println!("Period:    {}s", output.period);
+    println!("Epoch:     {}", output.epoch);
+    if output.offset_seconds != 0 {
+        println!("Offset:    {:+}s", output.offset_seconds);
+    }
+    if let Some(note) = output.note {
+        println!("Note:      {}", note);
+    }
+    if let Some(preview) = &output.secret_preview {
+        println!("Secret:    {}", preview);
+    }
+    println!(
+        "Code:      {} (expires in {}s)",
+        output.code, output.remaining_seconds
+    );
+    Ok(())
+}
+
+fn verify_code(
+    name: &str,
+    code: &str,
+    window: u32,
+    backend: &dyn StorageBackend,
+) -> Result<(), AppError> {
+    let account = get_account(name, backend)?;
+    let duration = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .expect("System time is before Unix epoch");
+
+    match verify_totp(&account, code, duration, window) {
+        Some(0) => println!("Code is valid for {} (current step)", name),
+        Some(offset) => println!("Code is valid for {} (step offset {:+})", name, offset),
+        None => {
+            return Err(AppError::InvalidInput(format!(
+                "Code is not valid for {} within ±{} step(s)",
+                name, window
+            )));
+        }
+    }
     Ok(())
 }
 
-fn load_qr_code_from_image(image_path: &str) -> Result<String, AppError> {
-    use image::ImageReader;
-    use rqrr::PreparedImage;
+fn rename_account(old: &str, new: &str, backend: &dyn StorageBackend) -> Result<(), AppError> {
+    let new = normalize_account_name(new)?;
+    let new = new.as_str();
+    update_storage(backend, |storage| {
+        let index = find_account_index(&storage.accounts, old)?;
+        // Renaming an account to a different casing of its own name is fine
+        if let Some(existing) = storage
+            .accounts
+            .iter()
+            .enumerate()
+            .find(|&(i, a)| i != index && same_name(&a.name, new))
+        {
+            return Err(AppError::InvalidInput(format!(
+                "Account '{}' already exists",
+                existing.1.name
+            )));
+        }
+        storage.accounts[index].name = new.to_string();
+        storage.accounts.sort_by(|a, b| a.name.cmp(&b.name));
+        Ok(())
+    })
+}
 
-    // Load and decode the image
-    let img = ImageReader::open(image_path)
-        .map_err(|e| AppError::new(format!("Failed to open image: {}", e)))?
-        .decode()
-        .map_err(|e| AppError::new(format!("Failed to decode image: {}", e)))?;
+/// Asks a y/N question on stderr, treating anything but "y" as no.
+fn confirm(prompt: &str) -> Result<bool, AppError> {
+    eprint!("{} [y/N] ", prompt);
+    io::stderr().flush()?;
 
-    // Convert to luma (grayscale) for QR code detection
-    let luma_img = img.to_luma8();
-    let mut prepared_img = PreparedImage::prepare(luma_img);
+    let mut input = String::new();
+    io::stdin().read_line(&mut input)?;
+    Ok(input.trim().eq_ignore_ascii_case("y"))
+}
 
-    // Find and decode QR codes
-    let grids = prepared_img.detect_grids();
-    if grids.is_empty() {
-        return Err(AppError::new("No QR code found in image"));
+fn export_uris(
+    backend: &dyn StorageBackend,
+    output: Option<&str>,
+    confirmed: bool,
+) -> Result<(), AppError> {
+    let storage = get_storage(backend)?;
+    if storage.accounts.is_empty() {
+        return Err(AppError::new("No accounts found"));
     }
 
-    // Decode the first QR code found
-    let (_, content) = grids[0]
-        .decode()
-        .map_err(|e| AppError::new(format!("Failed to decode QR code: {:?}", e)))?;
+    if !confirmed && !confirm("This prints every secret in plaintext. Continue?")? {
+        return Err(AppError::new("Export cancelled"));
+    }
 
-    Ok(content)
+    let uris: String = storage
+        .accounts
+        .iter()
+        .map(|account| format!("{}\n", account.generate_uri()))
+        .collect();
+
+    if let Some(path) = output {
+        write_file_atomically(Path::new(path), |file| file.write_all(uris.as_bytes()))
+            .map_err(|e| AppError::Storage(format!("Failed to write file {}: {}", path, e)))?;
+        println!("Exported {} account(s) to {}", storage.accounts.len(), path);
+    } else {
+        print!("{}", uris);
+    }
+    Ok(())
 }
 
-fn parse_otpauth_uri(uri: &str) -> Result<(String, String, String), AppError> {
-    if !uri.starts_with("otpauth://totp/") {
-        return Err(AppError::new("Invalid otpauth URI format"));
+/// Writes every account's settings as CSV to `path`, or to stdout for `-`.
+/// Secrets are only included, after confirmation, with `include_secrets`.
+fn export_csv(
+    backend: &dyn StorageBackend,
+    path: &str,
+    include_secrets: bool,
+    confirmed: bool,
+) -> Result<(), AppError> {
+    let storage = get_storage(backend)?;
+    if storage.accounts.is_empty() {
+        return Err(AppError::new("No accounts found"));
     }
 
-    // Parse the URI manually
-    let url =
-        url::Url::parse(uri).map_err(|e| AppError::new(format!("Failed to parse URI: {}", e)))?;
+    if include_secrets
+        && !confirmed
+        && !confirm("This writes every secret in plaintext. Continue?")?
+    {
+        return Err(AppError::new("Export cancelled"));
+    }
 
-    // Extract account name from path
-    let path = url.path().trim_start_matches('/');
-    let account_name = if path.contains(':') {
-        // Format: issuer:account or account
-        path.split(':').next_back().unwrap_or(path).to_string()
+    let csv = csv_accounts::write_accounts_csv(&storage.accounts, include_secrets)?;
+    if path == "-" {
+        io::stdout().write_all(&csv)?;
     } else {
-        path.to_string()
+        write_file_atomically(Path::new(path), |file| file.write_all(&csv))
+            .map_err(|e| AppError::Storage(format!("Failed to write file {}: {}", path, e)))?;
+        println!("Exported {} account(s) to {}", storage.accounts.len(), path);
+    }
+    Ok(())
+}
+
+/// Accounts whose names fuzzy-match `query` with their scores, best match
+/// first. Accounts with equal scores keep their stored order.
+fn rank_accounts<'a>(
+    accounts: &'a [Account],
+    query: &str,
+    matcher: &SkimMatcherV2,
+) -> Vec<(i64, &'a Account)> {
+    let mut matches: Vec<_> = accounts
+        .iter()
+        .filter_map(|account| {
+            matcher
+                .fuzzy_match(&account.name, query)
+                .map(|score| (score, account))
+        })
+        .collect();
+    matches.sort_by_key(|(score, _)| -score);
+    matches
+}
+
+fn search_accounts(
+    query: &str,
+    backend: &dyn StorageBackend,
+    json: bool,
+    limit: Option<usize>,
+    copy_top: bool,
+) -> Result<(), AppError> {
+    let storage = get_storage(backend)?;
+    let matcher = SkimMatcherV2::default();
+    let mut matches = rank_accounts(&storage.accounts, query, &matcher);
+    if let Some(limit) = limit {
+        matches.truncate(limit);
+    }
+    let Some((_, top)) = matches.first() else {
+        return Err(AppError::NotFound(query.to_string()));
     };
 
-    // Extract secret from query parameters
-    let mut secret = String::new();
-    let mut issuer = String::new();
+    if copy_top {
+        return show_code(&top.name, backend, false, false, true, false);
+    }
+    if json {
+        let entries: Vec<_> = matches
+            .iter()
+            .map(|(score, a)| serde_json::json!({ "name": a.name, "score": score }))
+            .collect();
+        println!("{}", serde_json::to_string_pretty(&entries)?);
+    } else {
+        for (_, account) in &matches {
+            println!("{}", account.name);
+        }
+    }
+    Ok(())
+}
+
+fn list_accounts(
+    backend: &dyn StorageBackend,
+    show_issuer: bool,
+    json: bool,
+) -> Result<(), AppError> {
+    let mut storage = get_storage(backend)?;
+    if storage.accounts.is_empty() {
+        return Err(AppError::new("No accounts found"));
+    }
+    storage.accounts.sort_by(|a, b| a.name.cmp(&b.name));
 
-    for (key, value) in url.query_pairs() {
-        match key.as_ref() {
-            "secret" => secret = value.to_string(),
-            "issuer" => issuer = value.to_string(),
-            _ => {}
+    if json {
+        let entries: Vec<_> = storage
+            .accounts
+            .iter()
+            .map(|a| {
+                if show_issuer {
+                    serde_json::json!({ "name": a.name, "issuer": a.issuer })
+                } else {
+                    serde_json::json!({ "name": a.name })
+                }
+            })
+            .collect();
+        println!("{}", serde_json::to_string_pretty(&entries)?);
+    } else {
+        for account in &storage.accounts {
+            if show_issuer {
+                println!("{} ({})", account.name, account.issuer);
+            } else {
+                println!("{}", account.name);
+            }
         }
     }
+    Ok(())
+}
 
-    if secret.is_empty() {
-        return Err(AppError::new("No secret found in otpauth URI"));
+fn handle_error(err: AppError) {
+    eprintln!("Error: {}", err);
+    if let Some(source) = err.source() {
+        eprintln!("Caused by: {}", source);
+    }
+}
+
+/// Adds the account(s) in the QR code(s) found in an image.
+#[cfg(feature = "qr")]
+fn add_from_image(
+    image_path: &str,
+    name: Option<&str>,
+    options: &AccountOptions,
+    replace: &ReplaceOptions,
+    backend: &dyn StorageBackend,
+) -> Result<(), AppError> {
+    let codes = qr::load_qr_codes_from_image(image_path)?;
+    add_from_qr_image(codes, name, options, replace, backend)
+}
+
+#[cfg(all(feature = "qr", feature = "clipboard"))]
+fn add_from_clipboard(
+    name: Option<&str>,
+    options: &AccountOptions,
+    replace: &ReplaceOptions,
+    backend: &dyn StorageBackend,
+) -> Result<(), AppError> {
+    let codes = qr::load_qr_codes_from_clipboard()?;
+    add_from_qr_image(codes, name, options, replace, backend)
+}
+
+#[cfg(not(all(feature = "qr", feature = "clipboard")))]
+fn add_from_clipboard(
+    _name: Option<&str>,
+    _options: &AccountOptions,
+    _replace: &ReplaceOptions,
+    _backend: &dyn StorageBackend,
+) -> Result<(), AppError> {
+    Err(AppError::InvalidInput(
+        "This build of hotpot can't read QR codes from the clipboard; rebuild it with the 'qr' and 'clipboard' features"
+            .to_string(),
+    ))
+}
+
+/// Adds the accounts in the QR codes decoded from one image. `name` can
+/// only be given when there is a single code.
+#[cfg(feature = "qr")]
+fn add_from_qr_image(
+    mut codes: Vec<Result<String, AppError>>,
+    name: Option<&str>,
+    options: &AccountOptions,
+    replace: &ReplaceOptions,
+    backend: &dyn StorageBackend,
+) -> Result<(), AppError> {
+    if codes.len() > 1 {
+        if name.is_some() {
+            return Err(AppError::InvalidInput(format!(
+                "The image contains {} QR codes; a name can only be given for a single code",
+                codes.len()
+            )));
+        }
+        return add_from_qr_codes(codes, options, replace, backend);
+    }
+
+    let uri = codes.remove(0)?;
+    if !migration::is_migration_uri(&uri) {
+        println!("Found otpauth URI: {}", uri);
+    }
+    add_from_uri(&uri, name, true, options, replace, backend)
+}
+
+#[cfg(not(feature = "qr"))]
+fn add_from_image(
+    _image_path: &str,
+    _name: Option<&str>,
+    _options: &AccountOptions,
+    _replace: &ReplaceOptions,
+    _backend: &dyn StorageBackend,
+) -> Result<(), AppError> {
+    Err(AppError::InvalidInput(
+        "This build of hotpot can't read QR code images; rebuild it with the 'qr' feature"
+            .to_string(),
+    ))
+}
+
+/// Adds an account for each QR code in an image, reporting the outcome of
+/// each one. Succeeds if at least one account was added.
+#[cfg(feature = "qr")]
+fn add_from_qr_codes(
+    codes: Vec<Result<String, AppError>>,
+    options: &AccountOptions,
+    replace: &ReplaceOptions,
+    backend: &dyn StorageBackend,
+) -> Result<(), AppError> {
+    let total = codes.len();
+    let mut added = 0;
+    for (index, code) in codes.into_iter().enumerate() {
+        println!("QR code {} of {}:", index + 1, total);
+        match code.and_then(|uri| add_from_uri(&uri, None, true, options, replace, backend)) {
+            Ok(()) => added += 1,
+            Err(e) => println!("  Skipped: {}", e),
+        }
+    }
+
+    println!("Added {} of {} QR code(s)", added, total);
+    if added == 0 {
+        return Err(AppError::InvalidInput(
+            "No accounts could be added from the image".to_string(),
+        ));
+    }
+    Ok(())
+}
+
+/// Gets a secret from the command line, stdin, or an interactive prompt, in
+/// that order of preference. Validation happens in `save_account`.
+fn read_secret(
+    secret: Option<&str>,
+    from_stdin: bool,
+    encoding: SecretEncoding,
+) -> Result<Zeroizing<String>, AppError> {
+    if let Some(secret) = secret {
+        return Ok(Zeroizing::new(secret.to_string()));
+    }
+    if from_stdin {
+        let mut input = Zeroizing::new(String::new());
+        io::stdin().read_line(&mut input)?;
+        return Ok(Zeroizing::new(input.trim().to_string()));
+    }
+    Ok(Zeroizing::new(prompt_password(format!(
+        "Enter the {} secret: ",
+        encoding
+    ))?))
+}
+
+/// Saves the account(s) described by an otpauth or otpauth-migration URI.
+///
+/// `name` overrides the URI's label. Otherwise the user is asked for a name
+/// when `confirm_name` is set or the URI has no usable label.
+fn add_from_uri(
+    uri: &str,
+    name: Option<&str>,
+    confirm_name: bool,
+    options: &AccountOptions,
+    replace: &ReplaceOptions,
+    backend: &dyn StorageBackend,
+) -> Result<(), AppError> {
+    if migration::is_migration_uri(uri) {
+        // A transfer code can hold many accounts, so there's no one account
+        // to name or annotate
+        if name.is_some() {
+            return Err(AppError::InvalidInput(
+                "An account name can't be given with an otpauth-migration URI, which may hold several accounts".to_string(),
+            ));
+        }
+        if options.note.is_some() {
+            return Err(AppError::InvalidInput(
+                "--note can't be used with an otpauth-migration URI, which may hold several accounts".to_string(),
+            ));
+        }
+        let batch = migration::parse_migration_uri(uri)?;
+        for account in &batch.accounts {
+            options.check_secret_length(account)?;
+        }
+        // --force replaces any of them that exist rather than asking about
+        // each one
+        let on_conflict = if replace.force {
+            OnConflict::Overwrite
+        } else {
+            OnConflict::default()
+        };
+        return import_migration_batch(batch, on_conflict, backend);
+    }
+    if !uri.starts_with("otpauth://") {
+        let scheme = uri.split_once("://").map_or(uri, |(scheme, _)| scheme);
+        return Err(AppError::InvalidInput(format!(
+            "Unsupported URI scheme '{}'; expected an otpauth:// URI",
+            scheme
+        )));
     }
 
-    // Use issuer if available, otherwise use a default
-    if issuer.is_empty() {
-        issuer = "Unknown".to_string();
+    warn_issuer_mismatch(uri);
+    let mut account = Account::from_otpauth_uri(uri)?;
+    // The other options conflict with --uri and --image
+    if let Some(note) = &options.note {
+        account.note = Some(note.clone());
     }
+    account.name = match name {
+        Some(name) => name.to_string(),
+        None if confirm_name || account.name.trim().is_empty() => {
+            prompt_account_name(&account.name)?
+        }
+        None => account.name.clone(),
+    };
 
-    Ok((account_name, secret, issuer))
+    options.check_secret_length(&account)?;
+    let issuer = account.issuer.clone();
+    let account_name = account.name.clone();
+    let replace = replace.allows_replacing(&account_name, backend)?;
+    save_account(account, replace, backend).map(|replaced| {
+        println!(
+            "{} account: {} (from {})",
+            added_or_replaced(replaced),
+            account_name,
+            issuer
+        )
+    })
 }
 
 fn prompt_account_name(default: &str) -> Result<String, AppError> {
@@ -244,6 +1957,103 @@ fn prompt_account_name(default: &str) -> Result<String, AppError> {
     }
 }
 
+/// Reports how far the system clock is from `server`'s, warning when it is
+/// off by more than the shortest period of any account.
+fn check_time(server: &str, backend: &dyn StorageBackend) -> Result<(), AppError> {
+    let offset = ntp::clock_offset(server, Duration::from_secs(5))?;
+    let storage = get_storage(backend)?;
+    let period = storage
+        .accounts
+        .iter()
+        .map(|account| account.period)
+        .min()
+        .unwrap_or(30);
+
+    println!("{}", describe_clock_offset(offset, server));
+    if offset.abs() > f64::from(period) {
+        eprintln!(
+            "Warning: that is more than the {}s period codes last, so they will likely be rejected; sync your system clock",
+            period
+        );
+    }
+    Ok(())
+}
+
+fn describe_clock_offset(offset: f64, server: &str) -> String {
+    // Round trips make anything finer than this noise
+    if offset.abs() < 0.05 {
+        return format!("The system clock matches {}", server);
+    }
+    let direction = if offset > 0.0 { "behind" } else { "ahead of" };
+    format!(
+        "The system clock is {:.1}s {} {}",
+        offset.abs(),
+        direction,
+        server
+    )
+}
+
+fn list_profiles() -> Result<(), AppError> {
+    let profiles = profile::list()?;
+    if profiles.is_empty() {
+        return Err(AppError::new("No profiles found"));
+    }
+    for (name, backends) in profiles {
+        println!("{} ({})", name, backends.join(", "));
+    }
+    Ok(())
+}
+
+const FILE_ENV: &str = "HOTPOT_FILE";
+#[cfg(feature = "dashboard")]
+const DEFAULT_CLIPBOARD_CLEAR_SECONDS: u64 = 20;
+#[cfg(feature = "dashboard")]
+const DEFAULT_REFRESH_MS: u64 = 250;
+/// Dashboard refresh intervals accepted from --refresh-ms and the config file
+const REFRESH_MS_RANGE: RangeInclusive<u64> = 50..=5000;
+
+/// Applies `--profile` and works out which file, if any, backs storage:
+/// `--file` wins over `HOTPOT_FILE`, which wins over the config file's
+/// `default_file`, and the keyring is used when none is set.
+fn resolve_file_path(cli: &Cli, config: &Config) -> Result<Option<String>, AppError> {
+    if let Some(name) = cli.profile.as_ref().or(config.default_profile.as_ref()) {
+        profile::set_active(name)?;
+    }
+    if let Some(name) = &cli.service {
+        profile::set_service(name)?;
+    }
+    let path = match &cli.file {
+        Some(Some(path)) => path.clone(),
+        Some(None) => profile::default_file_path()?.to_string_lossy().into_owned(),
+        None => match std::env::var(FILE_ENV) {
+            Ok(path) if !path.is_empty() => {
+                validate_file_path(&path).map_err(|e| {
+                    let explain = |message: String| {
+                        format!(
+                            "{} (from {}; pass --file to use another file, or unset {} to use the keyring)",
+                            message, FILE_ENV, FILE_ENV
+                        )
+                    };
+                    match e {
+                        AppError::InvalidInput(message) => {
+                            AppError::InvalidInput(explain(message))
+                        }
+                        AppError::Storage(message) => AppError::Storage(explain(message)),
+                        other => other,
+                    }
+                })?;
+                return Ok(Some(path));
+            }
+            _ => match &config.default_file {
+                Some(path) => path.to_string_lossy().into_owned(),
+                None => return Ok(None),
+            },
+        },
+    };
+    validate_file_path(&path)?;
+    Ok(Some(path))
+}
+
 fn validate_file_path(path: &str) -> Result<(), AppError> {
     let path_obj = Path::new(path);
 
@@ -251,7 +2061,7 @@ fn validate_file_path(path: &str) -> Result<(), AppError> {
     if let Some(parent) = path_obj.parent() {
         if !parent.exists() {
             fs::create_dir_all(parent).map_err(|e| {
-                AppError::new(format!(
+                AppError::Storage(format!(
                     "Cannot create directory '{}': {}",
                     parent.display(),
                     e
@@ -263,7 +2073,7 @@ fn validate_file_path(path: &str) -> Result<(), AppError> {
     // Check if file is readable/writable if it exists
     if path_obj.exists() {
         if path_obj.is_dir() {
-            return Err(AppError::new(format!(
+            return Err(AppError::InvalidInput(format!(
                 "'{}' is a directory, not a file",
                 path
             )));
@@ -271,10 +2081,13 @@ fn validate_file_path(path: &str) -> Result<(), AppError> {
 
         // Try to read the file to check permissions - but only if it exists
         let metadata = fs::metadata(path_obj)
-            .map_err(|e| AppError::new(format!("Cannot access file '{}': {}", path, e)))?;
+            .map_err(|e| AppError::Storage(format!("Cannot access file '{}': {}", path, e)))?;
 
         if !metadata.is_file() {
-            return Err(AppError::new(format!("'{}' is not a regular file", path)));
+            return Err(AppError::InvalidInput(format!(
+                "'{}' is not a regular file",
+                path
+            )));
         }
     }
 
@@ -282,87 +2095,633 @@ fn validate_file_path(path: &str) -> Result<(), AppError> {
 }
 
 fn main() {
-    let cli = Cli::parse();
-    let file_path = cli.file.as_deref();
-
-    // Validate file path if provided
-    if let Some(path) = file_path {
-        if let Err(err) = validate_file_path(path) {
+    let cli = Cli::try_parse().unwrap_or_else(|err| {
+        // --help and --version also arrive here and should exit normally
+        if !err.use_stderr() {
+            err.exit();
+        }
+        let _ = err.print();
+        // Usage errors count as invalid input; see EXIT_CODES_HELP
+        std::process::exit(3);
+    });
+    let (config, file_path) = match Config::load()
+        .and_then(|config| resolve_file_path(&cli, &config).map(|path| (config, path)))
+    {
+        Ok(resolved) => resolved,
+        Err(err) => {
+            let code = err.exit_code();
             handle_error(err);
-            std::process::exit(1);
+            std::process::exit(code);
         }
-    }
+    };
+    let backend: Box<dyn StorageBackend> = match file_path {
+        Some(path) => Box::new(FileBackend::new(path)),
+        None => keyring_backend(),
+    };
+    let backend = backend.as_ref();
 
     let result = match &cli.command {
-        None => dashboard::show(file_path),
-        Some(Commands::Add { name, image }) => {
+        #[cfg(feature = "dashboard")]
+        None => {
+            let clear_seconds = cli
+                .clipboard_clear_seconds
+                .or(config.clipboard_clear_seconds)
+                .unwrap_or(DEFAULT_CLIPBOARD_CLEAR_SECONDS);
+            let clear_after = (clear_seconds > 0).then(|| Duration::from_secs(clear_seconds));
+            let refresh_ms = cli
+                .refresh_ms
+                .or(config.refresh_ms)
+                .unwrap_or(DEFAULT_REFRESH_MS);
+            let refresh = Duration::from_millis(refresh_ms);
+            theme::Theme::load(cli.no_color, config.theme.as_deref()).and_then(|theme| {
+                dashboard::show(backend, theme, clear_after, refresh, cli.show_uri, cli.group)
+            })
+        }
+        #[cfg(not(feature = "dashboard"))]
+        None => Err(AppError::InvalidInput(
+            "This build of hotpot has no interactive dashboard; run 'hotpot --help' to see the available commands"
+                .to_string(),
+        )),
+        Some(Commands::Add {
+            name,
+            image,
+            uri,
+            from_clipboard,
+            secret,
+            secret_stdin,
+            options,
+            replace,
+        }) => {
             if let Some(image_path) = image {
-                // Load account from QR code image
-                match load_qr_code_from_image(image_path) {
-                    Ok(uri) => {
-                        println!("Found otpauth URI: {}", uri);
-                        match parse_otpauth_uri(&uri) {
-                            Ok((default_name, secret, issuer)) => {
-                                // Use provided name or prompt for name with default from QR code
-                                match if let Some(provided_name) = name {
-                                    Ok(provided_name.clone())
-                                } else {
-                                    prompt_account_name(&default_name)
-                                } {
-                                    Ok(account_name) => {
-                                        save_account(&account_name, &secret, file_path).map(|_| {
-                                            println!(
-                                                "Added account: {} (from {})",
-                                                account_name, issuer
-                                            )
-                                        })
-                                    }
-                                    Err(e) => Err(e),
-                                }
-                            }
-                            Err(e) => Err(e),
-                        }
-                    }
-                    Err(e) => Err(e),
-                }
+                add_from_image(image_path, name.as_deref(), options, replace, backend)
+            } else if *from_clipboard {
+                add_from_clipboard(name.as_deref(), options, replace, backend)
+            } else if let Some(uri) = uri {
+                add_from_uri(uri, name.as_deref(), false, options, replace, backend)
             } else {
                 // Traditional secret input - name is required
                 if let Some(account_name) = name {
-                    match prompt_password("Enter the Base32 secret: ") {
-                        Ok(secret) => save_account(account_name, &secret, file_path)
-                            .map(|_| println!("Added account: {}", account_name)),
-                        Err(err) => Err(AppError::new(err.to_string())),
-                    }
+                    read_secret(
+                        secret.as_deref(),
+                        *secret_stdin,
+                        options.encoding.unwrap_or_default(),
+                    ).and_then(|secret| {
+                        let account = options.build(
+                            account_name,
+                            &secret,
+                            config.default_algorithm.as_deref(),
+                        )?;
+                        options.check_secret_length(&account)?;
+                        let replace = replace.allows_replacing(account_name, backend)?;
+                        save_account(account, replace, backend).map(|replaced| {
+                            println!("{} account: {}", added_or_replaced(replaced), account_name)
+                        })
+                    })
                 } else {
-                    Err(AppError::new(
-                        "Account name is required when not using --image",
+                    Err(AppError::InvalidInput(
+                        "Account name is required when not using --image, --uri or --from-clipboard"
+                            .to_string(),
                     ))
                 }
             }
         }
-        Some(Commands::Code { name }) => get_account(name, file_path).and_then(|account| {
-            let duration = SystemTime::now()
-                .duration_since(UNIX_EPOCH)
-                .expect("System time is before Unix epoch");
-            generate_totp(&account, duration).map(|code| {
-                println!(
-                    "Code for {}: {:0width$}",
-                    name,
-                    code,
-                    width = account.digits as usize
-                );
+        Some(Commands::Search {
+            query,
+            json,
+            limit,
+            copy_top,
+        }) => search_accounts(query, backend, *json, *limit, *copy_top),
+        Some(Commands::Code {
+            name,
+            all,
+            json,
+            quiet,
+            copy,
+            watch,
+            window,
+        }) => match name {
+            _ if *all => show_all_codes(backend, *json, *quiet, cli.group),
+            Some(name) if *window > 0 => get_account(name, backend)
+                .and_then(|account| show_code_window(&account, *window, cli.group)),
+            Some(name) if *watch => {
+                get_account(name, backend).and_then(|account| watch_code(&account, cli.group))
+            }
+            Some(name) => show_code(name, backend, *json, *quiet, *copy, cli.group),
+            // clap requires a name unless --all is given
+            None => Ok(()),
+        },
+        Some(Commands::Info { name, json, reveal }) => show_info(name, backend, *json, *reveal),
+        Some(Commands::Verify { name, code, window }) => verify_code(name, code, *window, backend),
+        Some(Commands::Delete { name, force }) => confirm_delete(name, *force, backend)
+            .and_then(|()| delete_account(name, backend))
+            .map(|_| println!("Deleted account: {}", name)),
+        Some(Commands::Update { name, options }) => get_account(name, backend)
+            .and_then(|mut account| {
+                options.apply(&mut account);
+                let name = account.name.clone();
+                update_account(account, backend).map(|_| name)
             })
-        }),
-        Some(Commands::Delete { name }) => {
-            delete_account(name, file_path).map(|_| println!("Deleted account: {}", name))
+            .map(|name| println!("Updated account: {}", name)),
+        Some(Commands::SetOffset { name, seconds }) => get_account(name, backend)
+            .and_then(|mut account| {
+                account.offset_seconds = *seconds;
+                let name = account.name.clone();
+                update_account(account, backend).map(|_| name)
+            })
+            .map(|name| println!("Set clock offset for {} to {:+}s", name, seconds)),
+        Some(Commands::Rename { old, new }) => rename_account(old, new, backend)
+            .map(|_| println!("Renamed account: {} -> {}", old, new)),
+        Some(Commands::List { show_issuer, json }) => list_accounts(backend, *show_issuer, *json),
+        Some(Commands::Import {
+            migration,
+            aegis,
+            andotp,
+            freeotp,
+            csv,
+            json,
+            items_path,
+            totp_path,
+            name_path,
+            conflicts,
+        }) => {
+            if let Some(uri) = migration {
+                import_migration(uri, conflicts.on_conflict(), backend)
+            } else if let Some(path) = aegis {
+                import_aegis(path, conflicts.on_conflict(), backend)
+            } else if let Some(path) = andotp {
+                import_andotp(path, conflicts.on_conflict(), backend)
+            } else if let Some(path) = freeotp {
+                import_uri_list(path, conflicts.on_conflict(), backend)
+            } else if let Some(path) = csv {
+                import_csv(path, conflicts.on_conflict(), backend)
+            } else if let (Some(path), Some(totp_path)) = (json, totp_path) {
+                let paths = json_accounts::JsonPaths {
+                    items: items_path.as_deref(),
+                    totp: totp_path,
+                    name: name_path.as_deref(),
+                };
+                import_json(path, &paths, conflicts.on_conflict(), backend)
+            } else {
+                Ok(())
+            }
         }
-        Some(Commands::ExportQr { name }) => {
-            get_account(name, file_path).and_then(|account| export_qr_code(name, &account.secret))
+        Some(Commands::Migrate {
+            to_file,
+            to_keyring: _,
+            to_keyring_entries,
+            force,
+        }) => match to_file {
+            Some(path) => validate_file_path(path)
+                .and_then(|_| migrate_storage(backend, &FileBackend::new(path.as_str()), *force)),
+            None if *to_keyring_entries => {
+                migrate_storage(backend, &KeyringEntriesBackend::new(), *force).and_then(|_| {
+                    // Nothing reads the single entry once per-account entries
+                    // exist, so don't leave a stale copy of every secret behind
+                    if backend.describe() == KeyringBackend.describe() {
+                        KeyringBackend.remove()
+                    } else {
+                        Ok(())
+                    }
+                })
+            }
+            None => migrate_storage(backend, keyring_backend().as_ref(), *force),
+        },
+        Some(Commands::SetPassphrase { passphrase_stdin }) => {
+            set_passphrase(*passphrase_stdin, backend)
+        }
+        Some(Commands::Reveal {
+            name,
+            yes,
+            passphrase_stdin,
+        }) => reveal_secret(name, *yes, *passphrase_stdin, backend),
+        Some(Commands::ClearPassphrase { passphrase_stdin }) => {
+            clear_passphrase(*passphrase_stdin, backend)
         }
+        Some(Commands::Backup { output }) => backup_storage(output, backend),
+        Some(Commands::Restore {
+            input,
+            merge: _,
+            replace,
+            conflicts,
+        }) => restore_storage(input, *replace, conflicts.on_conflict(), backend),
+        Some(Commands::Export {
+            uris: _,
+            csv: Some(path),
+            include_secrets,
+            output: _,
+            yes_i_understand_this_exposes_secrets,
+        }) => export_csv(
+            backend,
+            path,
+            *include_secrets,
+            *yes_i_understand_this_exposes_secrets,
+        ),
+        Some(Commands::Export {
+            uris: _,
+            csv: None,
+            include_secrets: _,
+            output,
+            yes_i_understand_this_exposes_secrets,
+        }) => export_uris(
+            backend,
+            output.as_deref(),
+            *yes_i_understand_this_exposes_secrets,
+        ),
+        Some(Commands::Profile {
+            command: ProfileCommands::List,
+        }) => list_profiles(),
+        Some(Commands::CheckTime { server }) => check_time(server, backend),
+        Some(Commands::Completions { shell }) => {
+            clap_complete::generate(
+                *shell,
+                &mut Cli::command(),
+                env!("CARGO_BIN_NAME"),
+                &mut io::stdout(),
+            );
+            Ok(())
+        }
+        #[cfg(feature = "qr")]
+        Some(Commands::ExportQr {
+            name,
+            all: _,
+            migration,
+            output_dir,
+            yes_i_understand_this_exposes_secrets,
+            format,
+            output,
+            module_size,
+            foreground,
+            background,
+            ecc,
+            quiet_zone,
+            show_uri,
+        }) => match (name, output_dir) {
+            (None, _) if *migration => qr::export_migration_qr_codes(
+                output_dir.as_deref(),
+                *module_size,
+                *ecc,
+                *quiet_zone,
+                *yes_i_understand_this_exposes_secrets,
+                backend,
+            ),
+            (Some(name), _) => {
+                let options = qr::QrExportOptions {
+                    format: *format,
+                    output: output.as_deref(),
+                    module_size: *module_size,
+                    foreground,
+                    background,
+                    ecc: *ecc,
+                    quiet_zone: *quiet_zone,
+                    show_uri: *show_uri,
+                };
+                get_account(name, backend)
+                    .and_then(|account| qr::export_qr_code(&account, &options))
+            }
+            (None, Some(dir)) => qr::export_all_qr_codes(
+                dir,
+                *module_size,
+                *ecc,
+                *quiet_zone,
+                *yes_i_understand_this_exposes_secrets,
+                backend,
+            ),
+            // clap requires --name, or --all with --migration or --output-dir
+            (None, None) => Ok(()),
+        },
+        #[cfg(not(feature = "qr"))]
+        Some(Commands::ExportQr { .. }) => Err(AppError::InvalidInput(
+            "This build of hotpot can't export QR codes; rebuild it with the 'qr' feature"
+                .to_string(),
+        )),
     };
 
     if let Err(err) = result {
+        let code = err.exit_code();
         handle_error(err);
-        std::process::exit(1);
+        std::process::exit(code);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::storage::InMemoryBackend;
+    use crate::totp::generate_totp;
+
+    // RFC 6238 SHA256 test secret ("12345678901234567890123456789012")
+    const SHA256_SECRET: &str = "GEZDGNBVGY3TQOJQGEZDGNBVGY3TQOJQGEZDGNBVGY3TQOJQGEZA";
+
+    #[test]
+    fn test_add_options_produce_rfc_vector() {
+        let temp_dir = tempfile::tempdir().unwrap();
+        let path = temp_dir.path().join("accounts.json");
+        let path = path.to_str().unwrap();
+
+        let cli = Cli::try_parse_from([
+            "hotpot",
+            "add",
+            "rfc",
+            "--secret",
+            SHA256_SECRET,
+            "--algorithm",
+            "SHA256",
+            "--digits",
+            "8",
+        ])
+        .unwrap();
+        let Some(Commands::Add { name, options, .. }) = cli.command else {
+            panic!("Expected the add command");
+        };
+        let account = options.build(&name.unwrap(), SHA256_SECRET, None).unwrap();
+        let backend = FileBackend::new(path);
+        save_account(account, false, &backend).unwrap();
+
+        let account = get_account("rfc", &backend).unwrap();
+        let code = generate_totp(&account, Duration::from_secs(59)).unwrap();
+        assert_eq!(account.format_code(code), "46119246");
+    }
+
+    #[test]
+    fn test_add_options_reject_invalid_values() {
+        for (flag, value) in [("--algorithm", "MD5"), ("--digits", "5"), ("--period", "0")] {
+            let result = Cli::try_parse_from(["hotpot", "add", "x", flag, value]);
+            assert!(result.is_err(), "{} {} should be rejected", flag, value);
+        }
+    }
+
+    #[test]
+    fn test_parse_hash_algorithm() {
+        assert_eq!(parse_hash_algorithm("sha-256").unwrap(), "SHA256");
+        assert!(parse_hash_algorithm("steam").is_err());
+    }
+
+    #[test]
+    fn test_epoch_date() {
+        assert_eq!(parse_epoch_date("1970-01-01T00:00:00Z").unwrap(), 0);
+        assert_eq!(
+            parse_epoch_date("2024-01-01T08:00:00+08:00").unwrap(),
+            1704067200
+        );
+        assert!(parse_epoch_date("2024-01-01").is_err());
+        assert!(parse_epoch_date("9999-01-01T00:00:00Z").is_err());
+        assert!(parse_epoch_date("1969-12-31T23:59:59Z").is_err());
+
+        assert_eq!(format_epoch(1704067200), "2024-01-01T00:00:00Z");
+        assert_eq!(format_epoch(u64::MAX), u64::MAX.to_string());
+    }
+
+    #[test]
+    fn test_write_code_window() {
+        // RFC 6238 SHA1 secret; 1111111109 is near the end of its step
+        let account = Account::builder("test", "GEZDGNBVGY3TQOJQGEZDGNBVGY3TQOJQ")
+            .digits(8)
+            .build()
+            .unwrap();
+        let code_window = |now, window| {
+            let mut out = Vec::new();
+            write_code_window(&mut out, &account, Duration::from_secs(now), window, false).unwrap();
+            String::from_utf8(out)
+                .unwrap()
+                .lines()
+                .map(str::to_string)
+                .collect::<Vec<_>>()
+        };
+        let lines = code_window(1111111109, 1);
+        assert_eq!(lines.len(), 3);
+        assert_eq!(lines[1], "   0  07081804  01:58:00-01:58:30 UTC (current)");
+        assert_eq!(lines[2], "  +1  14050471  01:58:30-01:59:00 UTC");
+        assert!(lines[0].starts_with("  -1  "));
+
+        // No steps before the epoch
+        let lines = code_window(10, 2);
+        assert_eq!(lines.len(), 3);
+        assert!(lines[0].starts_with("   0  "));
+    }
+
+    #[test]
+    fn test_describe_clock_offset() {
+        assert_eq!(
+            describe_clock_offset(0.01, "pool.ntp.org"),
+            "The system clock matches pool.ntp.org"
+        );
+        assert_eq!(
+            describe_clock_offset(42.25, "pool.ntp.org"),
+            "The system clock is 42.2s behind pool.ntp.org"
+        );
+        assert_eq!(
+            describe_clock_offset(-3.0, "time.example"),
+            "The system clock is 3.0s ahead of time.example"
+        );
+    }
+
+    #[test]
+    fn test_parse_uri_list() {
+        let data = "otpauth://totp/GitHub:alice?secret=JBSWY3DPEHPK3PXP&issuer=GitHub\n\
+                    \n\
+                    otpauth://hotp/counter?secret=JBSWY3DPEHPK3PXP&counter=1\n\
+                    otpauth://totp/google?secret=JBSWY3DPEHPK3PXP&digits=8\r\n\
+                    otpauth://totp/mangled?issuer=Broken\n";
+        let (accounts, skipped) = parse_uri_list(data);
+
+        let names: Vec<_> = accounts.iter().map(|a| a.name.as_str()).collect();
+        assert_eq!(names, ["alice", "google"]);
+        assert_eq!(accounts[0].issuer, "GitHub");
+        assert_eq!(accounts[1].digits, 8);
+        assert_eq!(skipped.len(), 2);
+        assert!(skipped[0].starts_with("line 3: "), "{}", skipped[0]);
+        assert!(skipped[1].starts_with("line 5: "), "{}", skipped[1]);
+    }
+
+    #[test]
+    fn test_save_account_rejects_invalid_secret() {
+        let temp_dir = tempfile::TempDir::new().unwrap();
+        let path = temp_dir.path().join("accounts.json");
+        let backend = FileBackend::new(path.to_str().unwrap());
+
+        let result = save_account(
+            Account::new("github".to_string(), "not base32!".to_string()),
+            false,
+            &backend,
+        );
+        assert!(result.is_err());
+        assert!(!path.exists(), "Nothing should be persisted");
+    }
+
+    #[test]
+    fn test_save_account_normalizes_spaced_secret() {
+        let temp_dir = tempfile::TempDir::new().unwrap();
+        let path = temp_dir.path().join("accounts.json");
+        let backend = FileBackend::new(path.to_str().unwrap());
+
+        save_account(
+            Account::new("github".to_string(), "jbsw y3dp ehpk 3pxp".to_string()),
+            false,
+            &backend,
+        )
+        .unwrap();
+        let account = get_account("github", &backend).unwrap();
+        assert_eq!(account.secret, "JBSWY3DPEHPK3PXP");
+    }
+
+    #[test]
+    fn test_account_changes_in_memory() {
+        let backend = InMemoryBackend::default();
+        let account = |name: &str| Account::new(name.to_string(), "JBSWY3DPEHPK3PXP".to_string());
+
+        save_account(account("google"), false, &backend).unwrap();
+        save_account(account("github"), false, &backend).unwrap();
+        assert!(save_account(account("github"), false, &backend).is_err());
+
+        rename_account("google", "work", &backend).unwrap();
+        assert!(matches!(
+            rename_account("google", "other", &backend),
+            Err(AppError::NotFound(_))
+        ));
+        delete_account("github", &backend).unwrap();
+
+        let names: Vec<String> = get_storage(&backend)
+            .unwrap()
+            .accounts
+            .into_iter()
+            .map(|a| a.name)
+            .collect();
+        assert_eq!(names, ["work"]);
+    }
+
+    #[test]
+    fn test_delete_accounts_is_all_or_nothing() {
+        let backend = InMemoryBackend::default();
+        for name in ["amazon", "github", "google"] {
+            save_account(
+                Account::new(name.to_string(), "JBSWY3DPEHPK3PXP".to_string()),
+                false,
+                &backend,
+            )
+            .unwrap();
+        }
+        let names = |backend: &InMemoryBackend| -> Vec<String> {
+            get_storage(backend)
+                .unwrap()
+                .accounts
+                .into_iter()
+                .map(|a| a.name)
+                .collect()
+        };
+
+        assert!(matches!(
+            delete_accounts(&["github", "missing"], &backend),
+            Err(AppError::NotFound(_))
+        ));
+        assert_eq!(names(&backend), ["amazon", "github", "google"]);
+
+        delete_accounts(&["google", "amazon"], &backend).unwrap();
+        assert_eq!(names(&backend), ["github"]);
+    }
+
+    #[test]
+    fn test_save_account_replaces_only_when_asked() {
+        let backend = InMemoryBackend::default();
+        let account_with_secret =
+            |name: &str, secret: &str| Account::new(name.to_string(), secret.to_string());
+        save_account(
+            account_with_secret("GitHub", "JBSWY3DPEHPK3PXP"),
+            false,
+            &backend,
+        )
+        .unwrap();
+
+        let rotated = || account_with_secret("github", "GEZDGNBVGY3TQOJQ");
+        assert!(save_account(rotated(), false, &backend).is_err());
+        assert!(save_account(rotated(), true, &backend).unwrap());
+        assert!(
+            !save_account(
+                account_with_secret("gitlab", "JBSWY3DPEHPK3PXP"),
+                true,
+                &backend
+            )
+            .unwrap()
+        );
+
+        let accounts = get_storage(&backend).unwrap().accounts;
+        assert_eq!(accounts.len(), 2);
+        assert_eq!(accounts[0].name, "github");
+        assert_eq!(accounts[0].secret, "GEZDGNBVGY3TQOJQ");
+    }
+
+    #[test]
+    fn test_account_names_are_trimmed() {
+        let backend = InMemoryBackend::default();
+        let account = |name: &str| Account::new(name.to_string(), "JBSWY3DPEHPK3PXP".to_string());
+
+        for name in ["", "   ", "\t\n"] {
+            let err = save_account(account(name), false, &backend).unwrap_err();
+            assert!(matches!(err, AppError::InvalidInput(_)));
+            assert_eq!(err.to_string(), "Account name cannot be empty");
+        }
+
+        save_account(account("github "), false, &backend).unwrap();
+        assert!(save_account(account("  github"), false, &backend).is_err());
+        assert!(rename_account("github", " ", &backend).is_err());
+
+        let storage = get_storage(&backend).unwrap();
+        assert_eq!(storage.accounts.len(), 1);
+        assert_eq!(storage.accounts[0].name, "github");
+    }
+
+    #[test]
+    fn test_account_names_are_case_insensitive() {
+        let backend = InMemoryBackend::default();
+        let account = |name: &str| Account::new(name.to_string(), "JBSWY3DPEHPK3PXP".to_string());
+
+        save_account(account("GitHub"), false, &backend).unwrap();
+        let err = save_account(account("github"), false, &backend).unwrap_err();
+        assert_eq!(err.to_string(), "Account 'GitHub' already exists");
+
+        // Lookups ignore case but the stored casing is kept
+        assert_eq!(get_account("github", &backend).unwrap().name, "GitHub");
+        rename_account("GITHUB", "Github", &backend).unwrap();
+        assert_eq!(get_account("GitHub", &backend).unwrap().name, "Github");
+        delete_account("github", &backend).unwrap();
+        assert!(get_storage(&backend).unwrap().accounts.is_empty());
+    }
+
+    #[test]
+    fn test_exact_name_wins_over_case_insensitive_match() {
+        // Stores written before names were case-insensitive may hold both
+        let account = |name: &str| Account::new(name.to_string(), "JBSWY3DPEHPK3PXP".to_string());
+        let backend = InMemoryBackend::new(Storage {
+            accounts: vec![account("GitHub"), account("github")],
+            ..Default::default()
+        });
+
+        assert_eq!(get_account("github", &backend).unwrap().name, "github");
+        assert_eq!(get_account("GitHub", &backend).unwrap().name, "GitHub");
+        assert!(matches!(
+            get_account("GITHUB", &backend),
+            Err(AppError::InvalidInput(_))
+        ));
+    }
+
+    #[test]
+    fn test_concurrent_saves_keep_every_account() {
+        let temp_dir = tempfile::TempDir::new().unwrap();
+        let path = temp_dir.path().join("accounts.json");
+
+        let handles: Vec<_> = (0..8)
+            .map(|i| {
+                let path = path.to_str().unwrap().to_string();
+                std::thread::spawn(move || {
+                    let account = Account::new(format!("account{}", i), "JBSWY3DPEHPK3PXP".into());
+                    save_account(account, false, &FileBackend::new(path))
+                })
+            })
+            .collect();
+        for handle in handles {
+            handle.join().unwrap().unwrap();
+        }
+
+        let storage = get_storage(&FileBackend::new(path.to_str().unwrap())).unwrap();
+        assert_eq!(storage.accounts.len(), 8);
     }
 }
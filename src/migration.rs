@@ -0,0 +1,333 @@
+use base32::Alphabet;
+use base64::Engine;
+use base64::engine::general_purpose::STANDARD;
+use prost::Message;
+
+use crate::AppError;
+use crate::totp::Account;
+#[cfg(feature = "qr")]
+use crate::totp::decode_secret;
+
+const MIGRATION_PREFIX: &str = "otpauth-migration://offline";
+// Google Authenticator only has 30-second TOTP codes
+#[cfg(feature = "qr")]
+const MIGRATION_PERIOD: u32 = 30;
+/// Most protobuf bytes put in one QR code. The base64 and URL encoding make
+/// the URI about 1.4 times longer, which still scans easily from a screen.
+#[cfg(feature = "qr")]
+const MAX_BATCH_BYTES: usize = 600;
+
+// Protobuf schema used by Google Authenticator's "Transfer accounts" export
+#[derive(Clone, PartialEq, Message)]
+struct MigrationPayload {
+    #[prost(message, repeated, tag = "1")]
+    otp_parameters: Vec<OtpParameters>,
+    #[prost(int32, tag = "2")]
+    version: i32,
+    #[prost(int32, tag = "3")]
+    batch_size: i32,
+    #[prost(int32, tag = "4")]
+    batch_index: i32,
+    #[prost(int32, tag = "5")]
+    batch_id: i32,
+}
+
+#[derive(Clone, PartialEq, Message)]
+struct OtpParameters {
+    #[prost(bytes = "vec", tag = "1")]
+    secret: Vec<u8>,
+    #[prost(string, tag = "2")]
+    name: String,
+    #[prost(string, tag = "3")]
+    issuer: String,
+    #[prost(int32, tag = "4")]
+    algorithm: i32,
+    #[prost(int32, tag = "5")]
+    digits: i32,
+    #[prost(int32, tag = "6")]
+    otp_type: i32,
+    #[prost(int64, tag = "7")]
+    counter: i64,
+}
+
+const OTP_TYPE_HOTP: i32 = 1;
+#[cfg(feature = "qr")]
+const OTP_TYPE_TOTP: i32 = 2;
+
+/// Accounts decoded from a single migration QR code.
+pub struct MigrationBatch {
+    pub accounts: Vec<Account>,
+    /// Entries that could not be converted, with the reason they were skipped
+    pub skipped: Vec<String>,
+    pub batch_index: i32,
+    pub batch_size: i32,
+}
+
+pub fn is_migration_uri(uri: &str) -> bool {
+    uri.starts_with(MIGRATION_PREFIX)
+}
+
+pub fn parse_migration_uri(uri: &str) -> Result<MigrationBatch, AppError> {
+    if !is_migration_uri(uri) {
+        return Err(AppError::InvalidInput(
+            "Invalid otpauth-migration URI format".to_string(),
+        ));
+    }
+
+    let url = url::Url::parse(uri)
+        .map_err(|e| AppError::InvalidInput(format!("Failed to parse URI: {}", e)))?;
+    let data = url
+        .query_pairs()
+        .find(|(key, _)| key == "data")
+        .map(|(_, value)| value.to_string())
+        .ok_or_else(|| {
+            AppError::InvalidInput("No data found in otpauth-migration URI".to_string())
+        })?;
+
+    let bytes = STANDARD
+        .decode(data.as_bytes())
+        .map_err(|e| AppError::InvalidInput(format!("Failed to decode migration data: {}", e)))?;
+    let payload = MigrationPayload::decode(bytes.as_slice())
+        .map_err(|e| AppError::InvalidInput(format!("Failed to parse migration payload: {}", e)))?;
+
+    let mut accounts = Vec::new();
+    let mut skipped = Vec::new();
+    for params in payload.otp_parameters {
+        match convert_parameters(&params) {
+            Ok(account) => accounts.push(account),
+            Err(reason) => skipped.push(format!("{}: {}", params.name, reason)),
+        }
+    }
+
+    Ok(MigrationBatch {
+        accounts,
+        skipped,
+        batch_index: payload.batch_index,
+        batch_size: payload.batch_size,
+    })
+}
+
+/// Migration URIs holding a set of accounts, split over as many QR codes as
+/// it takes.
+#[cfg(feature = "qr")]
+pub struct MigrationExport {
+    pub uris: Vec<String>,
+    /// Accounts Google Authenticator can't represent, with the reason
+    pub skipped: Vec<String>,
+}
+
+/// Builds the `otpauth-migration` URIs that Google Authenticator's "Transfer
+/// accounts" scanner reads. Every batch shares `batch_id`, which is how the
+/// app knows they belong together.
+#[cfg(feature = "qr")]
+pub fn build_migration_uris(accounts: &[Account], batch_id: i32) -> MigrationExport {
+    let mut batches: Vec<Vec<OtpParameters>> = Vec::new();
+    let mut skipped = Vec::new();
+    for account in accounts {
+        let params = match to_parameters(account) {
+            Ok(params) => params,
+            Err(reason) => {
+                skipped.push(format!("{}: {}", account.name, reason));
+                continue;
+            }
+        };
+        match batches.last_mut() {
+            Some(batch)
+                if batch.iter().map(Message::encoded_len).sum::<usize>() + params.encoded_len()
+                    <= MAX_BATCH_BYTES =>
+            {
+                batch.push(params)
+            }
+            _ => batches.push(vec![params]),
+        }
+    }
+
+    let batch_size = batches.len() as i32;
+    let uris = batches
+        .into_iter()
+        .enumerate()
+        .map(|(index, otp_parameters)| {
+            migration_uri(&MigrationPayload {
+                otp_parameters,
+                version: 1,
+                batch_size,
+                batch_index: index as i32,
+                batch_id,
+            })
+        })
+        .collect();
+    MigrationExport { uris, skipped }
+}
+
+#[cfg(any(feature = "qr", test))]
+fn migration_uri(payload: &MigrationPayload) -> String {
+    let data = STANDARD.encode(payload.encode_to_vec());
+    format!("{}?data={}", MIGRATION_PREFIX, urlencoding::encode(&data))
+}
+
+/// The reverse of `convert_parameters`, for settings the format can hold.
+#[cfg(feature = "qr")]
+fn to_parameters(account: &Account) -> Result<OtpParameters, String> {
+    let algorithm = match account.algorithm.as_str() {
+        "SHA1" => 1,
+        "SHA256" => 2,
+        "SHA512" => 3,
+        _ => return Err(format!("{} codes are not supported", account.algorithm)),
+    };
+    let digits = match account.digits {
+        6 => 1,
+        8 => 2,
+        _ => return Err(format!("{}-digit codes are not supported", account.digits)),
+    };
+    if account.period != MIGRATION_PERIOD {
+        return Err(format!("{}-second codes are not supported", account.period));
+    }
+    if account.epoch != 0 {
+        return Err("codes counted from a custom epoch are not supported".to_string());
+    }
+    let secret = decode_secret(&account.secret, account.encoding).map_err(|e| e.to_string())?;
+    Ok(OtpParameters {
+        secret: secret.to_vec(),
+        name: account.name.clone(),
+        issuer: account.issuer.clone(),
+        algorithm,
+        digits,
+        otp_type: OTP_TYPE_TOTP,
+        counter: 0,
+    })
+}
+
+fn convert_parameters(params: &OtpParameters) -> Result<Account, String> {
+    if params.otp_type == OTP_TYPE_HOTP {
+        return Err("HOTP accounts are not supported".to_string());
+    }
+
+    // Names are often exported as "Issuer:account"
+    let name = params
+        .name
+        .split(':')
+        .next_back()
+        .unwrap_or(&params.name)
+        .trim()
+        .to_string();
+    let secret = base32::encode(Alphabet::RFC4648 { padding: false }, &params.secret);
+
+    let mut account = Account::new(name, secret);
+    if !params.issuer.is_empty() {
+        account.issuer = params.issuer.clone();
+    }
+    account.algorithm = match params.algorithm {
+        0 | 1 => "SHA1",
+        2 => "SHA256",
+        3 => "SHA512",
+        _ => return Err("unsupported algorithm".to_string()),
+    }
+    .to_string();
+    account.digits = match params.digits {
+        0 | 1 => 6,
+        2 => 8,
+        _ => return Err("unsupported digit count".to_string()),
+    };
+    Ok(account)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn params(name: &str, algorithm: i32, digits: i32, otp_type: i32) -> OtpParameters {
+        OtpParameters {
+            secret: b"Hello!\xde\xad\xbe\xef".to_vec(),
+            name: name.to_string(),
+            issuer: "Example".to_string(),
+            algorithm,
+            digits,
+            otp_type,
+            counter: 0,
+        }
+    }
+
+    #[test]
+    fn test_parse_migration_uri() {
+        let payload = MigrationPayload {
+            otp_parameters: vec![
+                params("Example:alice", 1, 1, 2),
+                params("bob", 2, 2, 2),
+                params("counter", 1, 1, OTP_TYPE_HOTP),
+            ],
+            version: 1,
+            batch_size: 2,
+            batch_index: 1,
+            batch_id: 42,
+        };
+
+        let batch = parse_migration_uri(&migration_uri(&payload)).unwrap();
+
+        assert_eq!(batch.batch_index, 1);
+        assert_eq!(batch.batch_size, 2);
+        assert_eq!(batch.accounts.len(), 2);
+        assert_eq!(batch.skipped.len(), 1);
+
+        let alice = &batch.accounts[0];
+        assert_eq!(alice.name, "alice");
+        assert_eq!(alice.issuer, "Example");
+        assert_eq!(alice.secret, "JBSWY3DPEHPK3PXP");
+        assert_eq!(alice.algorithm, "SHA1");
+        assert_eq!(alice.digits, 6);
+
+        let bob = &batch.accounts[1];
+        assert_eq!(bob.algorithm, "SHA256");
+        assert_eq!(bob.digits, 8);
+    }
+
+    #[test]
+    #[cfg(feature = "qr")]
+    fn test_build_migration_uris() {
+        let mut accounts: Vec<Account> = (0..30)
+            .map(|i| {
+                let mut account = Account::new(
+                    format!("user{}@example.com", i),
+                    "JBSWY3DPEHPK3PXP".to_string(),
+                );
+                account.issuer = "Example".to_string();
+                account
+            })
+            .collect();
+        accounts[0].algorithm = "SHA512".to_string();
+        accounts[1].digits = 8;
+        accounts[2].period = 60;
+        accounts[3].digits = 7;
+
+        let export = build_migration_uris(&accounts, 7);
+        assert_eq!(export.skipped.len(), 2);
+        assert!(export.skipped[0].starts_with("user2@example.com: 60-second"));
+        assert!(
+            export.uris.len() > 1,
+            "30 accounts should need several codes"
+        );
+
+        // Every batch round-trips through the importer and knows its place
+        let mut imported = Vec::new();
+        for (index, uri) in export.uris.iter().enumerate() {
+            let batch = parse_migration_uri(uri).unwrap();
+            assert_eq!(batch.batch_index, index as i32);
+            assert_eq!(batch.batch_size, export.uris.len() as i32);
+            assert!(batch.skipped.is_empty());
+            imported.extend(batch.accounts);
+        }
+        assert_eq!(imported.len(), 28);
+        assert_eq!(imported[0].algorithm, "SHA512");
+        assert_eq!(imported[1].digits, 8);
+        assert_eq!(imported[2].name, "user4@example.com");
+        assert_eq!(imported[2].issuer, "Example");
+        assert_eq!(imported[2].secret, "JBSWY3DPEHPK3PXP");
+
+        assert!(build_migration_uris(&[], 7).uris.is_empty());
+    }
+
+    #[test]
+    fn test_parse_migration_uri_without_data() {
+        assert!(parse_migration_uri("otpauth-migration://offline?foo=bar").is_err());
+        assert!(parse_migration_uri("otpauth://totp/github?secret=ABC").is_err());
+    }
+}
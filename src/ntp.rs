@@ -0,0 +1,153 @@
+use std::net::UdpSocket;
+use std::sync::mpsc;
+use std::thread;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+use crate::AppError;
+
+pub const DEFAULT_SERVER: &str = "pool.ntp.org";
+
+/// Seconds from the NTP epoch (1900-01-01) to the Unix epoch
+const NTP_UNIX_OFFSET: f64 = 2_208_988_800.0;
+const PACKET_LEN: usize = 48;
+
+/// Asks an NTP server for the time and returns how many seconds the local
+/// clock is behind it; negative when the local clock is ahead. `server` is
+/// a host name with an optional port. Gives up after `timeout`, including
+/// while looking the host up.
+pub fn clock_offset(server: &str, timeout: Duration) -> Result<f64, AppError> {
+    let address = if server.contains(':') {
+        server.to_string()
+    } else {
+        format!("{}:123", server)
+    };
+    // Name lookups can't be given a timeout, so the whole query runs on a
+    // thread that is abandoned if it takes too long
+    let (sender, receiver) = mpsc::channel();
+    thread::spawn(move || {
+        let _ = sender.send(query(&address, timeout));
+    });
+    receiver
+        .recv_timeout(timeout)
+        .map_err(|_| AppError::new(format!("No reply from {} within {:?}", server, timeout)))?
+        .map_err(|e| AppError::new(format!("Could not get the time from {}: {}", server, e)))
+}
+
+fn query(address: &str, timeout: Duration) -> Result<f64, String> {
+    let socket = UdpSocket::bind("0.0.0.0:0").map_err(|e| e.to_string())?;
+    socket
+        .set_read_timeout(Some(timeout))
+        .map_err(|e| e.to_string())?;
+    socket.connect(address).map_err(|e| e.to_string())?;
+
+    let sent = now();
+    socket.send(&request(sent)).map_err(|e| e.to_string())?;
+    let mut reply = [0; PACKET_LEN];
+    let len = socket.recv(&mut reply).map_err(|e| e.to_string())?;
+    offset_from_reply(&reply[..len], sent, now())
+}
+
+fn now() -> f64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .expect("System time is before Unix epoch")
+        .as_secs_f64()
+}
+
+/// An SNTP client request (RFC 4330) whose transmit timestamp is `sent`,
+/// which the server echoes back so its reply can be matched up.
+fn request(sent: f64) -> [u8; PACKET_LEN] {
+    let mut packet = [0; PACKET_LEN];
+    // Leap indicator 0, version 4, mode 3 (client)
+    packet[0] = 0b00_100_011;
+    packet[40..48].copy_from_slice(&to_timestamp(sent).to_be_bytes());
+    packet
+}
+
+/// Works out the clock offset from a server's reply, given the local times
+/// the request was sent and the reply received, as Unix seconds.
+fn offset_from_reply(reply: &[u8], sent: f64, received: f64) -> Result<f64, String> {
+    if reply.len() < PACKET_LEN {
+        return Err("reply is too short".to_string());
+    }
+    let timestamp = |at: usize| u64::from_be_bytes(reply[at..at + 8].try_into().unwrap());
+    if reply[0] & 0b111 != 4 {
+        return Err("reply is not from a server".to_string());
+    }
+    // Stratum 0 marks a "kiss-o'-death" refusal, e.g. for rate limiting
+    if reply[1] == 0 {
+        return Err("server refused the request".to_string());
+    }
+    if timestamp(24) != to_timestamp(sent) {
+        return Err("reply does not match the request".to_string());
+    }
+    let server_received = from_timestamp(timestamp(32));
+    let server_sent = from_timestamp(timestamp(40));
+    Ok(((server_received - sent) + (server_sent - received)) / 2.0)
+}
+
+/// Converts Unix seconds to a 64-bit NTP timestamp: whole seconds since 1900
+/// in the upper half and the fraction in the lower half.
+fn to_timestamp(unix: f64) -> u64 {
+    let ntp = unix + NTP_UNIX_OFFSET;
+    let seconds = ntp.trunc() as u64;
+    let fraction = (ntp.fract() * 4_294_967_296.0) as u64;
+    (seconds << 32) | fraction
+}
+
+fn from_timestamp(timestamp: u64) -> f64 {
+    let mut seconds = (timestamp >> 32) as f64;
+    // The seconds wrap in 2036; small values belong to the next era
+    if timestamp >> 63 == 0 {
+        seconds += 4_294_967_296.0;
+    }
+    seconds + (timestamp & 0xffff_ffff) as f64 / 4_294_967_296.0 - NTP_UNIX_OFFSET
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn reply(sent: f64, server_received: f64, server_sent: f64) -> [u8; PACKET_LEN] {
+        let mut packet = [0; PACKET_LEN];
+        packet[0] = 0b00_100_100; // Version 4, mode 4 (server)
+        packet[1] = 2;
+        packet[24..32].copy_from_slice(&to_timestamp(sent).to_be_bytes());
+        packet[32..40].copy_from_slice(&to_timestamp(server_received).to_be_bytes());
+        packet[40..48].copy_from_slice(&to_timestamp(server_sent).to_be_bytes());
+        packet
+    }
+
+    #[test]
+    fn test_timestamp_round_trip() {
+        let unix = 1_700_000_000.25;
+        assert!((from_timestamp(to_timestamp(unix)) - unix).abs() < 1e-6);
+        // The first second after the 2036 rollover
+        assert!((from_timestamp(0) - 2_085_978_496.0).abs() < 1e-6);
+    }
+
+    #[test]
+    fn test_offset_from_reply() {
+        // The server is 5s ahead and each leg of the trip takes 0.1s
+        let sent = 1_700_000_000.0;
+        let packet = reply(sent, sent + 5.1, sent + 5.2);
+        let offset = offset_from_reply(&packet, sent, sent + 0.3).unwrap();
+        assert!((offset - 5.0).abs() < 1e-3, "{}", offset);
+
+        assert!(offset_from_reply(&packet[..40], sent, sent).is_err());
+        assert!(offset_from_reply(&packet, sent + 1.0, sent + 1.0).is_err());
+        let mut refused = packet;
+        refused[1] = 0;
+        assert!(offset_from_reply(&refused, sent, sent).is_err());
+    }
+
+    #[test]
+    fn test_request() {
+        let packet = request(1_700_000_000.5);
+        assert_eq!(packet[0] & 0b111, 3);
+        assert_eq!(
+            u64::from_be_bytes(packet[40..48].try_into().unwrap()),
+            to_timestamp(1_700_000_000.5)
+        );
+    }
+}
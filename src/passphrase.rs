@@ -0,0 +1,40 @@
+use argon2::Argon2;
+use argon2::password_hash::rand_core::OsRng;
+use argon2::password_hash::{PasswordHash, PasswordHasher, PasswordVerifier, SaltString};
+
+use crate::AppError;
+
+/// Hashes a passphrase into a PHC string such as `$argon2id$v=19$...`.
+pub fn hash(passphrase: &str) -> Result<String, AppError> {
+    let salt = SaltString::generate(&mut OsRng);
+    Argon2::default()
+        .hash_password(passphrase.as_bytes(), &salt)
+        .map(|hash| hash.to_string())
+        .map_err(|e| AppError::new(format!("Failed to hash passphrase: {}", e)))
+}
+
+/// Checks a passphrase against a hash produced by `hash`.
+pub fn verify(passphrase: &str, hash: &str) -> Result<bool, AppError> {
+    let parsed = PasswordHash::new(hash)
+        .map_err(|e| AppError::Storage(format!("Stored passphrase hash is invalid: {}", e)))?;
+    Ok(Argon2::default()
+        .verify_password(passphrase.as_bytes(), &parsed)
+        .is_ok())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_hash_and_verify() {
+        let hashed = hash("correct horse").unwrap();
+        assert!(hashed.starts_with("$argon2"));
+        assert!(verify("correct horse", &hashed).unwrap());
+        assert!(!verify("wrong horse", &hashed).unwrap());
+
+        // Each hash gets its own salt
+        assert_ne!(hashed, hash("correct horse").unwrap());
+        assert!(verify("anything", "not a hash").is_err());
+    }
+}
@@ -0,0 +1,183 @@
+use std::collections::BTreeMap;
+use std::error::Error;
+use std::fs;
+use std::path::PathBuf;
+use std::sync::OnceLock;
+
+use directories::ProjectDirs;
+use keyring::Entry;
+
+use crate::{AppError, SERVICE_NAME, STORAGE_KEY, storage};
+
+const DEFAULT_PROFILE: &str = "default";
+// Keyring entries can't be enumerated, so profiles stored in the keyring are
+// recorded here for `profile list`
+const PROFILES_KEY: &str = "_hotpot_profiles";
+
+static ACTIVE_PROFILE: OnceLock<String> = OnceLock::new();
+static SERVICE: OnceLock<String> = OnceLock::new();
+
+/// Selects the profile used by every storage operation for the rest of the
+/// process. Only the first call has any effect.
+pub fn set_active(name: &str) -> Result<(), AppError> {
+    validate_name(name)?;
+    let _ = ACTIVE_PROFILE.set(name.to_string());
+    Ok(())
+}
+
+// Names end up in file names and keyring keys, so keep them simple
+fn validate_name(name: &str) -> Result<(), AppError> {
+    if name.is_empty()
+        || !name
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_')
+    {
+        return Err(AppError::InvalidInput(format!(
+            "Invalid profile name '{}': use only letters, digits, '-' and '_'",
+            name
+        )));
+    }
+    Ok(())
+}
+
+/// Selects the keyring service used for the rest of the process instead of
+/// `hotpot`. Only the first call has any effect.
+pub fn set_service(name: &str) -> Result<(), AppError> {
+    if name.trim().is_empty() {
+        return Err(AppError::InvalidInput(
+            "Keyring service name cannot be empty".to_string(),
+        ));
+    }
+    let _ = SERVICE.set(name.to_string());
+    Ok(())
+}
+
+/// Keyring service that holds every entry hotpot writes.
+pub fn service() -> &'static str {
+    SERVICE.get().map_or(SERVICE_NAME, String::as_str)
+}
+
+fn active() -> Option<&'static str> {
+    ACTIVE_PROFILE.get().map(String::as_str)
+}
+
+/// Keyring entry holding the active profile's accounts.
+pub fn storage_key() -> String {
+    storage_key_for(active())
+}
+
+/// Keyring entry holding the accounts of profile `name`, or of the unnamed
+/// store for `None`. The default profile is the unnamed store, just as its
+/// file is the one used without `--profile`.
+pub fn storage_key_for(name: Option<&str>) -> String {
+    match name {
+        Some(name) if name != DEFAULT_PROFILE => format!("{}_{}", STORAGE_KEY, name),
+        _ => STORAGE_KEY.to_string(),
+    }
+}
+
+/// Directory holding hotpot's configuration, e.g. `~/.config/hotpot`.
+pub fn config_dir() -> Result<PathBuf, AppError> {
+    ProjectDirs::from("", "", "hotpot")
+        .map(|dirs| dirs.config_dir().to_path_buf())
+        .ok_or_else(|| AppError::Storage("Could not find a configuration directory".to_string()))
+}
+
+/// File used by `--file` without a path, e.g. `~/.config/hotpot/work.json`.
+pub fn default_file_path() -> Result<PathBuf, AppError> {
+    let name = active().unwrap_or(DEFAULT_PROFILE);
+    Ok(config_dir()?.join(format!("{}.json", name)))
+}
+
+fn registered_profiles(entry: &Entry) -> Result<Vec<String>, AppError> {
+    match entry.get_password() {
+        Ok(data) => Ok(serde_json::from_str(&data)?),
+        Err(keyring::Error::NoEntry) => Ok(Vec::new()),
+        Err(e) => Err(AppError::from(e)),
+    }
+}
+
+/// Records the active profile as having keyring storage.
+pub fn register_keyring_profile() -> Result<(), AppError> {
+    // The default profile is the unnamed store, which `list` finds itself
+    let Some(name) = active().filter(|name| *name != DEFAULT_PROFILE) else {
+        return Ok(());
+    };
+    let entry = Entry::new(service(), PROFILES_KEY)?;
+    let mut names = registered_profiles(&entry)?;
+    if !names.iter().any(|n| n == name) {
+        names.push(name.to_string());
+        names.sort();
+        entry.set_password(&serde_json::to_string(&names)?)?;
+    }
+    Ok(())
+}
+
+/// Known profiles, mapped to the backends that hold accounts for them.
+pub fn list() -> Result<BTreeMap<String, Vec<&'static str>>, AppError> {
+    let mut profiles: BTreeMap<String, Vec<&'static str>> = BTreeMap::new();
+
+    match Entry::new(service(), PROFILES_KEY)
+        .map_err(AppError::from)
+        .and_then(|entry| registered_profiles(&entry))
+    {
+        Ok(names) => {
+            for name in names {
+                profiles.entry(name).or_default().push("keyring");
+            }
+            // The unnamed store predates profiles, so it was never registered
+            if storage::keyring_store_exists(STORAGE_KEY).unwrap_or(false) {
+                profiles
+                    .entry(DEFAULT_PROFILE.to_string())
+                    .or_default()
+                    .push("keyring");
+            }
+        }
+        Err(e) => {
+            let cause = e
+                .source()
+                .map_or_else(|| e.to_string(), |cause| cause.to_string());
+            eprintln!(
+                "Warning: could not read profiles from the keyring: {}",
+                cause
+            );
+        }
+    }
+
+    let dir = config_dir()?;
+    if dir.is_dir() {
+        let entries = fs::read_dir(&dir).map_err(|e| {
+            AppError::Storage(format!("Failed to read directory {}: {}", dir.display(), e))
+        })?;
+        for entry in entries.flatten() {
+            let path = entry.path();
+            if path.extension().is_some_and(|ext| ext == "json") {
+                if let Some(name) = path.file_stem().and_then(|stem| stem.to_str()) {
+                    profiles.entry(name.to_string()).or_default().push("file");
+                }
+            }
+        }
+    }
+
+    Ok(profiles)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_validate_name() {
+        assert!(validate_name("work").is_ok());
+        assert!(validate_name("side-project_2").is_ok());
+        assert!(validate_name("").is_err());
+        assert!(validate_name("../escape").is_err());
+        assert!(validate_name("with space").is_err());
+    }
+
+    #[test]
+    fn test_default_profile_is_the_unnamed_store() {
+        assert_eq!(storage_key_for(Some("default")), storage_key_for(None));
+        assert_ne!(storage_key_for(Some("work")), storage_key_for(None));
+    }
+}
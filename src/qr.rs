@@ -0,0 +1,461 @@
+use std::collections::HashSet;
+use std::fs;
+use std::io::{self, IsTerminal};
+use std::path::Path;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use crate::migration::build_migration_uris;
+use crate::storage::{StorageBackend, get_storage};
+use crate::totp::Account;
+use crate::{QrEcc, QrFormat, confirm};
+use hotpot::AppError;
+use qrcode::{EcLevel, QrCode};
+
+impl From<QrEcc> for EcLevel {
+    fn from(ecc: QrEcc) -> Self {
+        match ecc {
+            QrEcc::L => EcLevel::L,
+            QrEcc::M => EcLevel::M,
+            QrEcc::Q => EcLevel::Q,
+            QrEcc::H => EcLevel::H,
+        }
+    }
+}
+
+pub struct QrExportOptions<'a> {
+    pub format: Option<QrFormat>,
+    pub output: Option<&'a str>,
+    pub module_size: u32,
+    pub foreground: &'a str,
+    pub background: &'a str,
+    pub ecc: QrEcc,
+    /// Width in modules of the light border around the code
+    pub quiet_zone: u32,
+    /// Also print the otpauth URI, which contains the secret in plain text
+    pub show_uri: bool,
+}
+
+pub fn export_qr_code(account: &Account, options: &QrExportOptions) -> Result<(), AppError> {
+    use qrcode::render::{Renderer, svg};
+
+    let format = resolve_qr_format(options.format, options.output)?;
+    if format == QrFormat::Png && options.output.is_none() {
+        return Err(AppError::InvalidInput(
+            "PNG export requires --output <PATH>".to_string(),
+        ));
+    }
+    if format == QrFormat::Svg {
+        validate_svg_color(options.foreground)?;
+        validate_svg_color(options.background)?;
+    }
+
+    let uri = account.generate_uri();
+    if options.show_uri {
+        println!("Generated URI: {}", uri);
+    }
+    let code = qr_code(&uri, options.ecc)?;
+    // The renderers are built by hand, as `QrCode::render` always draws a
+    // four-module quiet zone
+    let colors = code.to_colors();
+
+    let rendered = match format {
+        QrFormat::Png => {
+            let path = options.output.unwrap_or_default();
+            save_qr_png(&code, path, options.module_size, options.quiet_zone)?;
+            println!("Saved QR code to {}", path);
+            return Ok(());
+        }
+        QrFormat::Svg => Renderer::<svg::Color>::new(&colors, code.width(), options.quiet_zone)
+            .module_dimensions(options.module_size, options.module_size)
+            .dark_color(svg::Color(options.foreground))
+            .light_color(svg::Color(options.background))
+            .build(),
+        QrFormat::Terminal => format!("\n{}", render_terminal(&code, options.quiet_zone)),
+    };
+
+    if let Some(path) = options.output {
+        fs::write(path, rendered)
+            .map_err(|e| AppError::Storage(format!("Failed to write file {}: {}", path, e)))?;
+        println!("Saved QR code to {}", path);
+    } else {
+        println!("{}", rendered);
+    }
+    Ok(())
+}
+
+/// Writes a PNG QR code for every account into `dir`, named after the
+/// account, and lists the files written.
+pub fn export_all_qr_codes(
+    dir: &str,
+    module_size: u32,
+    ecc: QrEcc,
+    quiet_zone: u32,
+    confirmed: bool,
+    backend: &dyn StorageBackend,
+) -> Result<(), AppError> {
+    let storage = get_storage(backend)?;
+    if storage.accounts.is_empty() {
+        return Err(AppError::new("No accounts found"));
+    }
+    if !confirmed
+        && !confirm(&format!(
+            "This writes {} secret(s) to {} as images. Continue?",
+            storage.accounts.len(),
+            dir
+        ))?
+    {
+        return Err(AppError::new("Export cancelled"));
+    }
+
+    fs::create_dir_all(dir)
+        .map_err(|e| AppError::Storage(format!("Cannot create directory '{}': {}", dir, e)))?;
+    let mut used = HashSet::new();
+    for account in &storage.accounts {
+        let file_name = unique_file_name(&sanitize_file_name(&account.name), "png", &mut used);
+        let path = Path::new(dir).join(file_name);
+        let path = path.to_string_lossy();
+        let code = qr_code(&account.generate_uri(), ecc)?;
+        save_qr_png(&code, &path, module_size, quiet_zone)?;
+        println!("{} -> {}", account.name, path);
+    }
+    println!("Exported {} QR code(s) to {}", storage.accounts.len(), dir);
+    Ok(())
+}
+
+/// Shows Google Authenticator "Transfer accounts" QR codes holding every
+/// account one after another, or writes them as PNGs into `dir`.
+pub fn export_migration_qr_codes(
+    dir: Option<&str>,
+    module_size: u32,
+    ecc: QrEcc,
+    quiet_zone: u32,
+    confirmed: bool,
+    backend: &dyn StorageBackend,
+) -> Result<(), AppError> {
+    let storage = get_storage(backend)?;
+    if storage.accounts.is_empty() {
+        return Err(AppError::new("No accounts found"));
+    }
+    // The app only uses the ID to tell transfers apart, so it needn't be random
+    let batch_id = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map_or(0, |now| now.subsec_nanos() as i32);
+    let export = build_migration_uris(&storage.accounts, batch_id);
+    for reason in &export.skipped {
+        println!("Skipped {}", reason);
+    }
+    if export.uris.is_empty() {
+        return Err(AppError::InvalidInput(
+            "None of the accounts can be moved to Google Authenticator".to_string(),
+        ));
+    }
+    let count = storage.accounts.len() - export.skipped.len();
+    let destination = dir.map_or("the screen".to_string(), |dir| dir.to_string());
+    if !confirmed
+        && !confirm(&format!(
+            "This writes {} secret(s) to {} as QR codes. Continue?",
+            count, destination
+        ))?
+    {
+        return Err(AppError::new("Export cancelled"));
+    }
+
+    if let Some(dir) = dir {
+        fs::create_dir_all(dir)
+            .map_err(|e| AppError::Storage(format!("Cannot create directory '{}': {}", dir, e)))?;
+    }
+    let total = export.uris.len();
+    for (index, uri) in export.uris.iter().enumerate() {
+        let code = qr_code(uri, ecc)?;
+        match dir {
+            Some(dir) => {
+                let path = Path::new(dir).join(format!("migration-{}.png", index + 1));
+                let path = path.to_string_lossy();
+                save_qr_png(&code, &path, module_size, quiet_zone)?;
+                println!("QR code {} of {} -> {}", index + 1, total, path);
+            }
+            None => {
+                println!("QR code {} of {}:\n", index + 1, total);
+                println!("{}", render_terminal(&code, quiet_zone));
+                // Give the phone time to scan each code before it scrolls away
+                if index + 1 < total && io::stdin().is_terminal() {
+                    println!("Press Enter for the next QR code...");
+                    let mut input = String::new();
+                    io::stdin().read_line(&mut input)?;
+                }
+            }
+        }
+    }
+    println!(
+        "Exported {} account(s) in {} QR code(s) for Google Authenticator",
+        count, total
+    );
+    Ok(())
+}
+
+fn render_terminal(code: &QrCode, quiet_zone: u32) -> String {
+    use qrcode::render::{Renderer, unicode};
+
+    // Light modules are drawn as blocks, so dark terminals show a dark code
+    Renderer::<unicode::Dense1x2>::new(&code.to_colors(), code.width(), quiet_zone)
+        .dark_color(unicode::Dense1x2::Light)
+        .light_color(unicode::Dense1x2::Dark)
+        .build()
+}
+
+fn qr_code(uri: &str, ecc: QrEcc) -> Result<QrCode, AppError> {
+    QrCode::with_error_correction_level(uri.as_bytes(), EcLevel::from(ecc))
+        .map_err(|e| AppError::new(format!("QR code error: {}", e)))
+}
+
+/// Replaces characters that aren't safe in file names on every platform.
+fn sanitize_file_name(name: &str) -> String {
+    let sanitized: String = name
+        .trim()
+        .chars()
+        .map(|c| {
+            if c.is_alphanumeric() || matches!(c, '-' | '_' | '.' | '@') {
+                c
+            } else {
+                '_'
+            }
+        })
+        .collect();
+    // Avoid hidden files and names made only of dots
+    let sanitized = sanitized.trim_start_matches('.');
+    if sanitized.is_empty() {
+        "account".to_string()
+    } else {
+        sanitized.to_string()
+    }
+}
+
+/// Appends `-2`, `-3`, ... to `stem` until it doesn't clash with a name in
+/// `used`, which is compared case-insensitively for case-insensitive file systems.
+fn unique_file_name(stem: &str, extension: &str, used: &mut HashSet<String>) -> String {
+    let mut name = format!("{}.{}", stem, extension);
+    let mut suffix = 2;
+    while !used.insert(name.to_lowercase()) {
+        name = format!("{}-{}.{}", stem, suffix, extension);
+        suffix += 1;
+    }
+    name
+}
+
+/// Picks the export format, inferring it from the output file extension when
+/// no explicit format was requested.
+fn resolve_qr_format(format: Option<QrFormat>, output: Option<&str>) -> Result<QrFormat, AppError> {
+    if let Some(format) = format {
+        return Ok(format);
+    }
+    let Some(path) = output else {
+        return Ok(QrFormat::Terminal);
+    };
+    let extension = Path::new(path)
+        .extension()
+        .and_then(|ext| ext.to_str())
+        .unwrap_or("");
+    if extension.eq_ignore_ascii_case("png") {
+        Ok(QrFormat::Png)
+    } else if extension.eq_ignore_ascii_case("svg") {
+        Ok(QrFormat::Svg)
+    } else {
+        Err(AppError::InvalidInput(format!(
+            "Unsupported output format '{}': expected a .png or .svg file",
+            path
+        )))
+    }
+}
+
+/// Colors are interpolated into SVG attributes, so only allow hex values and
+/// plain color names.
+fn validate_svg_color(color: &str) -> Result<(), AppError> {
+    let valid = match color.strip_prefix('#') {
+        Some(hex) => matches!(hex.len(), 3 | 6) && hex.chars().all(|c| c.is_ascii_hexdigit()),
+        None => !color.is_empty() && color.chars().all(|c| c.is_ascii_alphabetic()),
+    };
+    if valid {
+        Ok(())
+    } else {
+        Err(AppError::InvalidInput(format!("Invalid color '{}'", color)))
+    }
+}
+
+fn save_qr_png(
+    code: &QrCode,
+    path: &str,
+    module_size: u32,
+    quiet_zone: u32,
+) -> Result<(), AppError> {
+    let img = qr_image(code, module_size, quiet_zone);
+    img.save_with_format(path, image::ImageFormat::Png)
+        .map_err(|e| AppError::Storage(format!("Failed to write image {}: {}", path, e)))
+}
+
+fn qr_image(code: &QrCode, module_size: u32, quiet_zone: u32) -> image::GrayImage {
+    use image::{GrayImage, Luma};
+    use qrcode::Color;
+
+    let width = code.width() as u32;
+    let colors = code.to_colors();
+    let size = (width + 2 * quiet_zone) * module_size;
+
+    GrayImage::from_fn(size, size, |x, y| {
+        let (mx, my) = (x / module_size, y / module_size);
+        let inside = (quiet_zone..quiet_zone + width).contains(&mx)
+            && (quiet_zone..quiet_zone + width).contains(&my);
+        if inside {
+            let index = ((my - quiet_zone) * width + (mx - quiet_zone)) as usize;
+            if colors[index] == Color::Dark {
+                return Luma([0]);
+            }
+        }
+        Luma([255])
+    })
+}
+
+/// Decodes every QR code found in an image, in the order they were detected.
+/// Each entry is the code's contents, or why that code couldn't be read.
+pub fn load_qr_codes_from_image(
+    image_path: &str,
+) -> Result<Vec<Result<String, AppError>>, AppError> {
+    use image::ImageReader;
+
+    // Load and decode the image
+    let img = ImageReader::open(image_path)
+        .map_err(|e| AppError::InvalidInput(format!("Failed to open image: {}", e)))?
+        .decode()
+        .map_err(|e| AppError::InvalidInput(format!("Failed to decode image: {}", e)))?;
+
+    // Convert to luma (grayscale) for QR code detection
+    decode_qr_codes(img.to_luma8())
+}
+
+/// Like `load_qr_codes_from_image`, for the image on the clipboard.
+#[cfg(feature = "clipboard")]
+pub fn load_qr_codes_from_clipboard() -> Result<Vec<Result<String, AppError>>, AppError> {
+    let mut clipboard = arboard::Clipboard::new()
+        .map_err(|e| AppError::new(format!("Failed to open the clipboard: {}", e)))?;
+    let data = match clipboard.get_image() {
+        Ok(data) => data,
+        Err(arboard::Error::ContentNotAvailable) => {
+            let held = if clipboard.get_text().is_ok() {
+                "text"
+            } else {
+                "no image"
+            };
+            return Err(AppError::InvalidInput(format!(
+                "The clipboard holds {}; copy a screenshot of the QR code first",
+                held
+            )));
+        }
+        Err(e) => {
+            return Err(AppError::new(format!(
+                "Failed to read the clipboard: {}",
+                e
+            )));
+        }
+    };
+    let img = clipboard_image(data)?;
+    decode_qr_codes(image::DynamicImage::ImageRgba8(img).to_luma8())
+}
+
+/// Converts an image read from the clipboard, which arboard gives as RGBA
+/// bytes.
+#[cfg(feature = "clipboard")]
+pub fn clipboard_image(data: arboard::ImageData) -> Result<image::RgbaImage, AppError> {
+    let width = u32::try_from(data.width).ok();
+    let height = u32::try_from(data.height).ok();
+    width
+        .zip(height)
+        .and_then(|(width, height)| {
+            image::RgbaImage::from_raw(width, height, data.bytes.into_owned())
+        })
+        .ok_or_else(|| AppError::new("The image on the clipboard could not be read"))
+}
+
+fn decode_qr_codes(luma_img: image::GrayImage) -> Result<Vec<Result<String, AppError>>, AppError> {
+    use rqrr::PreparedImage;
+
+    let mut prepared_img = PreparedImage::prepare(luma_img);
+
+    // Find and decode QR codes
+    let grids = prepared_img.detect_grids();
+    if grids.is_empty() {
+        return Err(AppError::InvalidInput(
+            "No QR code found in image".to_string(),
+        ));
+    }
+
+    Ok(grids
+        .iter()
+        .map(|grid| {
+            grid.decode()
+                .map(|(_, content)| content)
+                .map_err(|e| AppError::InvalidInput(format!("Failed to decode QR code: {:?}", e)))
+        })
+        .collect())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_qr_file_names() {
+        assert_eq!(sanitize_file_name("alice@example.com"), "alice@example.com");
+        assert_eq!(
+            sanitize_file_name("GitHub: work/alice"),
+            "GitHub__work_alice"
+        );
+        assert_eq!(sanitize_file_name("../.."), "_..");
+        assert_eq!(sanitize_file_name(".."), "account");
+
+        let mut used = HashSet::new();
+        assert_eq!(unique_file_name("github", "png", &mut used), "github.png");
+        assert_eq!(unique_file_name("GitHub", "png", &mut used), "GitHub-2.png");
+        assert_eq!(unique_file_name("github", "png", &mut used), "github-3.png");
+    }
+
+    #[test]
+    fn test_qr_ecc_and_quiet_zone() {
+        let uri = "otpauth://totp/github?secret=JBSWY3DPEHPK3PXP&issuer=hotpot";
+        let low = qr_code(uri, QrEcc::L).unwrap();
+        let high = qr_code(uri, QrEcc::H).unwrap();
+        assert_eq!(low.error_correction_level(), EcLevel::L);
+        assert_eq!(high.error_correction_level(), EcLevel::H);
+        // More error correction needs more modules for the same data
+        assert!(high.width() > low.width());
+
+        let width = low.width() as u32;
+        assert_eq!(qr_image(&low, 2, 0).width(), width * 2);
+        let image = qr_image(&low, 2, 3);
+        assert_eq!(image.width(), (width + 6) * 2);
+        assert!(image.pixels().take(6).all(|pixel| pixel.0 == [255]));
+    }
+
+    #[cfg(feature = "clipboard")]
+    #[test]
+    fn test_decode_clipboard_image() {
+        let uri = "otpauth://totp/github?secret=JBSWY3DPEHPK3PXP&issuer=hotpot";
+        let gray = qr_image(&qr_code(uri, QrEcc::M).unwrap(), 4, 4);
+        let rgba = image::DynamicImage::ImageLuma8(gray).to_rgba8();
+        let data = arboard::ImageData {
+            width: rgba.width() as usize,
+            height: rgba.height() as usize,
+            bytes: rgba.into_raw().into(),
+        };
+
+        let img = clipboard_image(data).unwrap();
+        let codes = decode_qr_codes(image::DynamicImage::ImageRgba8(img).to_luma8()).unwrap();
+        assert_eq!(codes.len(), 1);
+        assert_eq!(codes[0].as_deref().unwrap(), uri);
+
+        let truncated = arboard::ImageData {
+            width: 10,
+            height: 10,
+            bytes: vec![0; 12].into(),
+        };
+        assert!(clipboard_image(truncated).is_err());
+    }
+}
@@ -0,0 +1,672 @@
+use std::cell::RefCell;
+use std::collections::BTreeMap;
+use std::fs;
+use std::io::{self, Write};
+use std::path::Path;
+use std::thread;
+use std::time::{Duration, Instant};
+
+use fs4::fs_std::FileExt;
+use keyring::Entry;
+use serde::{Deserialize, Serialize};
+use zeroize::Zeroizing;
+
+use crate::profile;
+use crate::totp::Account;
+use hotpot::AppError;
+
+#[derive(Serialize, Deserialize, Default, Clone)]
+pub struct Storage {
+    pub accounts: Vec<Account>,
+    /// Argon2 hash of the passphrase that unlocks the dashboard, if one is set
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub passphrase_hash: Option<String>,
+}
+
+/// Somewhere accounts can be loaded from and saved to.
+///
+/// Account settings aren't validated on load, so one bad account can still be
+/// fixed or deleted; generate_totp reports it when a code is requested.
+pub trait StorageBackend {
+    /// Reads the store, which is empty if nothing has been saved yet.
+    fn load(&self) -> Result<Storage, AppError>;
+
+    /// Replaces the store's contents with `storage`.
+    fn save(&self, storage: &Storage) -> Result<(), AppError>;
+
+    /// Keeps other processes from changing the store until the returned lock
+    /// is dropped. Backends that serialize access themselves return `None`.
+    fn lock(&self, _exclusive: bool) -> Result<Option<StorageLock>, AppError> {
+        Ok(None)
+    }
+
+    /// Where the store lives, for messages such as "Migrated 3 account(s) to ...".
+    fn describe(&self) -> String;
+}
+
+/// Stores every account as one JSON entry in the OS keyring, under the
+/// active service and profile.
+pub struct KeyringBackend;
+
+impl StorageBackend for KeyringBackend {
+    fn load(&self) -> Result<Storage, AppError> {
+        let entry =
+            Entry::new(profile::service(), &profile::storage_key()).map_err(AppError::from)?;
+
+        match entry.get_password() {
+            Ok(data) => Ok(serde_json::from_str(&Zeroizing::new(data))?),
+            Err(keyring::Error::NoEntry) => Ok(Storage::default()),
+            Err(e) => Err(AppError::from(e)),
+        }
+    }
+
+    fn save(&self, storage: &Storage) -> Result<(), AppError> {
+        // The serialized store holds every secret in plain text
+        let data = Zeroizing::new(serde_json::to_string_pretty(storage)?);
+        Entry::new(profile::service(), &profile::storage_key())?.set_password(&data)?;
+        profile::register_keyring_profile()
+    }
+
+    fn describe(&self) -> String {
+        "the keyring".to_string()
+    }
+}
+
+impl KeyringBackend {
+    /// Deletes the single entry, once its accounts have moved elsewhere.
+    pub fn remove(&self) -> Result<(), AppError> {
+        KeyringEntries.delete(&profile::storage_key())
+    }
+}
+
+/// Somewhere `KeyringEntriesBackend` can keep named entries; the keyring
+/// outside tests.
+trait EntryStore {
+    fn get(&self, key: &str) -> Result<Option<Zeroizing<String>>, AppError>;
+    fn set(&self, key: &str, value: &str) -> Result<(), AppError>;
+    /// Deleting an entry that doesn't exist is not an error.
+    fn delete(&self, key: &str) -> Result<(), AppError>;
+}
+
+struct KeyringEntries;
+
+impl EntryStore for KeyringEntries {
+    fn get(&self, key: &str) -> Result<Option<Zeroizing<String>>, AppError> {
+        match Entry::new(profile::service(), key)?.get_password() {
+            Ok(data) => Ok(Some(Zeroizing::new(data))),
+            Err(keyring::Error::NoEntry) => Ok(None),
+            Err(e) => Err(AppError::from(e)),
+        }
+    }
+
+    fn set(&self, key: &str, value: &str) -> Result<(), AppError> {
+        Entry::new(profile::service(), key)?.set_password(value)?;
+        Ok(())
+    }
+
+    fn delete(&self, key: &str) -> Result<(), AppError> {
+        match Entry::new(profile::service(), key)?.delete_password() {
+            Ok(()) | Err(keyring::Error::NoEntry) => Ok(()),
+            Err(e) => Err(AppError::from(e)),
+        }
+    }
+}
+
+/// Lists the accounts held by `KeyringEntriesBackend`, along with the
+/// settings that belong to the store rather than to an account.
+#[derive(Serialize, Deserialize, Default)]
+struct KeyringIndex {
+    accounts: Vec<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    passphrase_hash: Option<String>,
+}
+
+/// Entries as last read or written by `KeyringEntriesBackend`.
+#[derive(Default)]
+struct StoredEntries {
+    index: Option<String>,
+    /// Serialized accounts by name; `None` for an entry that couldn't be
+    /// parsed, which is kept as it is so it can still be recovered by hand
+    accounts: BTreeMap<String, Option<Zeroizing<String>>>,
+}
+
+/// Stores each account as its own keyring entry, with an index entry listing
+/// their names. Saving only writes the accounts that changed, and a damaged
+/// entry costs one account rather than the whole store.
+pub struct KeyringEntriesBackend {
+    entries: Box<dyn EntryStore>,
+    /// The profile's single-entry key, which its entries' keys start with
+    storage_key: String,
+    stored: RefCell<Option<StoredEntries>>,
+}
+
+impl KeyringEntriesBackend {
+    pub fn new() -> Self {
+        Self::with_entries(Box::new(KeyringEntries), profile::storage_key())
+    }
+
+    fn with_entries(entries: Box<dyn EntryStore>, storage_key: String) -> Self {
+        Self {
+            entries,
+            storage_key,
+            stored: RefCell::new(None),
+        }
+    }
+
+    /// Whether the active profile's accounts have been moved to
+    /// per-account entries.
+    pub fn exists() -> Result<bool, AppError> {
+        Ok(KeyringEntries.get(&Self::new().index_key())?.is_some())
+    }
+
+    fn index_key(&self) -> String {
+        index_key(&self.storage_key)
+    }
+
+    fn account_key(&self, name: &str) -> String {
+        format!("{}:account:{}", self.storage_key, name)
+    }
+}
+
+// Profile names can't contain ':', so the per-account layout's keys can't
+// be taken by another profile's
+fn index_key(storage_key: &str) -> String {
+    format!("{}:index", storage_key)
+}
+
+/// Whether the keyring holds accounts under `storage_key`, in either the
+/// single-entry or the per-account layout.
+pub fn keyring_store_exists(storage_key: &str) -> Result<bool, AppError> {
+    Ok(KeyringEntries.get(storage_key)?.is_some()
+        || KeyringEntries.get(&index_key(storage_key))?.is_some())
+}
+
+impl StorageBackend for KeyringEntriesBackend {
+    fn load(&self) -> Result<Storage, AppError> {
+        let mut stored = StoredEntries::default();
+        let mut storage = Storage::default();
+        if let Some(index) = self.entries.get(&self.index_key())? {
+            let parsed: KeyringIndex = serde_json::from_str(&index)?;
+            storage.passphrase_hash = parsed.passphrase_hash;
+            stored.index = Some(index.to_string());
+
+            for name in parsed.accounts {
+                let Some(data) = self.entries.get(&self.account_key(&name))? else {
+                    eprintln!("Warning: account '{}' is missing from the keyring", name);
+                    continue;
+                };
+                match serde_json::from_str(&data) {
+                    Ok(account) => {
+                        storage.accounts.push(account);
+                        stored.accounts.insert(name, Some(data));
+                    }
+                    Err(e) => {
+                        eprintln!("Warning: skipping damaged account '{}': {}", name, e);
+                        stored.accounts.insert(name, None);
+                    }
+                }
+            }
+        }
+        *self.stored.borrow_mut() = Some(stored);
+        Ok(storage)
+    }
+
+    fn save(&self, storage: &Storage) -> Result<(), AppError> {
+        if self.stored.borrow().is_none() {
+            self.load()?;
+        }
+        let previous = self.stored.take().unwrap_or_default();
+
+        let mut stored = StoredEntries::default();
+        for account in &storage.accounts {
+            let data = Zeroizing::new(serde_json::to_string(account)?);
+            let unchanged = matches!(
+                previous.accounts.get(&account.name),
+                Some(Some(old)) if **old == *data
+            );
+            if !unchanged {
+                self.entries.set(&self.account_key(&account.name), &data)?;
+            }
+            stored.accounts.insert(account.name.clone(), Some(data));
+        }
+        let mut removed = Vec::new();
+        for (name, data) in previous.accounts {
+            if stored.accounts.contains_key(&name) {
+                continue;
+            }
+            match data {
+                Some(_) => removed.push(name),
+                None => {
+                    stored.accounts.insert(name, None);
+                }
+            }
+        }
+
+        // The index is written after the accounts it lists and before the
+        // removed ones are deleted, so an interrupted save loses nothing
+        let index = serde_json::to_string(&KeyringIndex {
+            accounts: stored.accounts.keys().cloned().collect(),
+            passphrase_hash: storage.passphrase_hash.clone(),
+        })?;
+        if previous.index.as_ref() != Some(&index) {
+            self.entries.set(&self.index_key(), &index)?;
+        }
+        stored.index = Some(index);
+        for name in removed {
+            self.entries.delete(&self.account_key(&name))?;
+        }
+
+        *self.stored.borrow_mut() = Some(stored);
+        profile::register_keyring_profile()
+    }
+
+    fn describe(&self) -> String {
+        "the keyring (one entry per account)".to_string()
+    }
+}
+
+/// Stores accounts in a plaintext JSON file.
+pub struct FileBackend {
+    path: String,
+}
+
+impl FileBackend {
+    pub fn new(path: impl Into<String>) -> Self {
+        Self { path: path.into() }
+    }
+
+    fn lock_within(
+        &self,
+        exclusive: bool,
+        timeout: Duration,
+    ) -> Result<Option<StorageLock>, AppError> {
+        let path = self.path.as_str();
+        // There is nothing to protect when reading a file that doesn't exist yet
+        if !exclusive && !Path::new(path).exists() {
+            return Ok(None);
+        }
+
+        if let Some(parent) = Path::new(path).parent() {
+            if !parent.as_os_str().is_empty() {
+                fs::create_dir_all(parent)
+                    .map_err(|e| AppError::Storage(format!("Failed to create directory: {}", e)))?;
+            }
+        }
+        let lock_path = format!("{}.lock", path);
+        let file = fs::OpenOptions::new()
+            .create(true)
+            .truncate(false)
+            .write(true)
+            .open(&lock_path)
+            .map_err(|e| {
+                AppError::Storage(format!("Failed to open lock file {}: {}", lock_path, e))
+            })?;
+
+        let start = Instant::now();
+        loop {
+            // Called through the trait, as newer std has inherent methods of
+            // the same names that need a newer Rust than CI's
+            let result = if exclusive {
+                FileExt::try_lock_exclusive(&file)
+            } else {
+                FileExt::try_lock_shared(&file)
+            };
+            match result {
+                Ok(true) => return Ok(Some(StorageLock { _file: file })),
+                Ok(false) if start.elapsed() < timeout => {
+                    thread::sleep(Duration::from_millis(50));
+                }
+                Ok(false) => {
+                    return Err(AppError::Storage(
+                        "Storage is locked by another process".to_string(),
+                    ));
+                }
+                Err(e) => {
+                    return Err(AppError::Storage(format!("Failed to lock {}: {}", path, e)));
+                }
+            }
+        }
+    }
+}
+
+impl StorageBackend for FileBackend {
+    fn load(&self) -> Result<Storage, AppError> {
+        let path = self.path.as_str();
+        if !Path::new(path).exists() {
+            return Ok(Storage::default());
+        }
+        let data = fs::read_to_string(path)
+            .map(Zeroizing::new)
+            .map_err(|e| AppError::Storage(format!("Failed to read file {}: {}", path, e)))?;
+        Ok(serde_json::from_str(&data)?)
+    }
+
+    fn save(&self, storage: &Storage) -> Result<(), AppError> {
+        let data = Zeroizing::new(serde_json::to_string_pretty(storage)?);
+        let path = self.path.as_str();
+        if let Some(parent) = Path::new(path).parent() {
+            fs::create_dir_all(parent)
+                .map_err(|e| AppError::Storage(format!("Failed to create directory: {}", e)))?;
+        }
+        write_file_atomically(Path::new(path), |file| file.write_all(data.as_bytes()))
+            .map_err(|e| AppError::Storage(format!("Failed to write file {}: {}", path, e)))
+    }
+
+    fn lock(&self, exclusive: bool) -> Result<Option<StorageLock>, AppError> {
+        self.lock_within(exclusive, LOCK_TIMEOUT)
+    }
+
+    fn describe(&self) -> String {
+        self.path.clone()
+    }
+}
+
+/// Keeps the store in memory, so tests can drive storage code without
+/// touching the keyring or the filesystem.
+#[cfg(test)]
+#[derive(Default)]
+pub struct InMemoryBackend {
+    storage: std::cell::RefCell<Storage>,
+}
+
+#[cfg(test)]
+impl InMemoryBackend {
+    pub fn new(storage: Storage) -> Self {
+        Self {
+            storage: std::cell::RefCell::new(storage),
+        }
+    }
+}
+
+#[cfg(test)]
+impl StorageBackend for InMemoryBackend {
+    fn load(&self) -> Result<Storage, AppError> {
+        Ok(self.storage.borrow().clone())
+    }
+
+    fn save(&self, storage: &Storage) -> Result<(), AppError> {
+        *self.storage.borrow_mut() = storage.clone();
+        Ok(())
+    }
+
+    fn describe(&self) -> String {
+        "memory".to_string()
+    }
+}
+
+/// Advisory lock on a file-backed store, released when dropped.
+///
+/// The lock is taken on a sidecar `<file>.lock` because atomic writes replace
+/// the accounts file itself.
+pub struct StorageLock {
+    _file: fs::File,
+}
+
+const LOCK_TIMEOUT: Duration = Duration::from_secs(5);
+
+pub fn get_storage(backend: &dyn StorageBackend) -> Result<Storage, AppError> {
+    let _lock = backend.lock(false)?;
+    backend.load()
+}
+
+/// Loads, modifies and saves storage while holding an exclusive lock, so
+/// concurrent invocations can't drop each other's changes.
+pub fn update_storage<T>(
+    backend: &dyn StorageBackend,
+    update: impl FnOnce(&mut Storage) -> Result<T, AppError>,
+) -> Result<T, AppError> {
+    let _lock = backend.lock(true)?;
+    let mut storage = backend.load()?;
+    let result = update(&mut storage)?;
+    backend.save(&storage)?;
+    Ok(result)
+}
+
+/// Writes to a temporary file in the destination directory and renames it into
+/// place, so a crash or full disk never leaves a half-written accounts file.
+/// The temporary file is created with owner-only (0600) permissions on Unix.
+pub fn write_file_atomically(
+    path: &Path,
+    write: impl FnOnce(&mut fs::File) -> io::Result<()>,
+) -> io::Result<()> {
+    let dir = match path.parent() {
+        Some(parent) if !parent.as_os_str().is_empty() => parent,
+        _ => Path::new("."),
+    };
+    let mut temp = tempfile::NamedTempFile::new_in(dir)?;
+    write(temp.as_file_mut())?;
+    temp.as_file().sync_all()?;
+    temp.persist(path).map_err(|e| e.error)?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_update_storage_saves_changes() {
+        let backend = InMemoryBackend::new(Storage::default());
+        let count = update_storage(&backend, |storage| {
+            storage.passphrase_hash = Some("hash".to_string());
+            Ok(storage.accounts.len())
+        })
+        .unwrap();
+        assert_eq!(count, 0);
+        assert_eq!(
+            get_storage(&backend).unwrap().passphrase_hash.as_deref(),
+            Some("hash")
+        );
+
+        // Nothing is saved when the update fails
+        let result: Result<(), AppError> = update_storage(&backend, |storage| {
+            storage.passphrase_hash = None;
+            Err(AppError::new("failed"))
+        });
+        assert!(result.is_err());
+        assert!(get_storage(&backend).unwrap().passphrase_hash.is_some());
+    }
+
+    /// Entries kept in memory, counting the writes made to them.
+    #[derive(Default)]
+    struct MemoryEntries {
+        values: RefCell<BTreeMap<String, String>>,
+        writes: std::cell::Cell<usize>,
+    }
+
+    impl EntryStore for std::rc::Rc<MemoryEntries> {
+        fn get(&self, key: &str) -> Result<Option<Zeroizing<String>>, AppError> {
+            Ok(self.values.borrow().get(key).cloned().map(Zeroizing::new))
+        }
+
+        fn set(&self, key: &str, value: &str) -> Result<(), AppError> {
+            self.writes.set(self.writes.get() + 1);
+            self.values
+                .borrow_mut()
+                .insert(key.to_string(), value.to_string());
+            Ok(())
+        }
+
+        fn delete(&self, key: &str) -> Result<(), AppError> {
+            self.values.borrow_mut().remove(key);
+            Ok(())
+        }
+    }
+
+    fn test_account(name: &str) -> Account {
+        Account::new(name.to_string(), "JBSWY3DPEHPK3PXP".to_string())
+    }
+
+    #[test]
+    fn test_keyring_entries_only_write_changes() {
+        let entries = std::rc::Rc::new(MemoryEntries::default());
+        let backend = KeyringEntriesBackend::with_entries(
+            Box::new(entries.clone()),
+            profile::storage_key_for(None),
+        );
+
+        update_storage(&backend, |storage| {
+            storage.accounts = vec![test_account("github"), test_account("google")];
+            Ok(())
+        })
+        .unwrap();
+        // Two accounts and the index
+        assert_eq!(entries.writes.get(), 3);
+        assert!(
+            entries
+                .values
+                .borrow()
+                .contains_key(&backend.account_key("github"))
+        );
+
+        // Adding an account writes it and the index, not the others
+        update_storage(&backend, |storage| {
+            storage.accounts.push(test_account("gitlab"));
+            Ok(())
+        })
+        .unwrap();
+        assert_eq!(entries.writes.get(), 5);
+
+        // Deleting one removes its entry
+        update_storage(&backend, |storage| {
+            storage.accounts.retain(|a| a.name != "google");
+            Ok(())
+        })
+        .unwrap();
+        assert!(
+            !entries
+                .values
+                .borrow()
+                .contains_key(&backend.account_key("google"))
+        );
+
+        let names: Vec<String> = KeyringEntriesBackend::with_entries(
+            Box::new(entries.clone()),
+            profile::storage_key_for(None),
+        )
+        .load()
+        .unwrap()
+        .accounts
+        .into_iter()
+        .map(|a| a.name)
+        .collect();
+        assert_eq!(names, ["github", "gitlab"]);
+    }
+
+    #[test]
+    fn test_keyring_entries_survive_a_damaged_entry() {
+        let entries = std::rc::Rc::new(MemoryEntries::default());
+        let backend = KeyringEntriesBackend::with_entries(
+            Box::new(entries.clone()),
+            profile::storage_key_for(None),
+        );
+        backend
+            .save(&Storage {
+                accounts: vec![test_account("github"), test_account("google")],
+                passphrase_hash: Some("hash".to_string()),
+            })
+            .unwrap();
+        entries
+            .values
+            .borrow_mut()
+            .insert(backend.account_key("google"), "not json".to_string());
+
+        let backend = KeyringEntriesBackend::with_entries(
+            Box::new(entries.clone()),
+            profile::storage_key_for(None),
+        );
+        let storage = backend.load().unwrap();
+        assert_eq!(storage.accounts.len(), 1);
+        assert_eq!(storage.passphrase_hash.as_deref(), Some("hash"));
+
+        // Saving keeps the damaged entry and its place in the index
+        backend.save(&storage).unwrap();
+        assert_eq!(
+            entries.values.borrow()[&backend.account_key("google")],
+            "not json"
+        );
+        assert!(entries.values.borrow()[&backend.index_key()].contains("google"));
+    }
+
+    #[test]
+    fn test_keyring_entries_keep_profiles_apart() {
+        // With '_' as the separator, profile "index"'s single entry was the
+        // default profile's index, and profile "account_github"'s was the
+        // default profile's entry for github
+        let entries = std::rc::Rc::new(MemoryEntries::default());
+        let backend = |profile| {
+            KeyringEntriesBackend::with_entries(
+                Box::new(entries.clone()),
+                profile::storage_key_for(profile),
+            )
+        };
+        backend(None)
+            .save(&Storage {
+                accounts: vec![test_account("github")],
+                passphrase_hash: None,
+            })
+            .unwrap();
+        for profile in ["index", "account_github"] {
+            entries
+                .values
+                .borrow_mut()
+                .insert(profile::storage_key_for(Some(profile)), "{}".to_string());
+            backend(Some(profile))
+                .save(&Storage {
+                    accounts: vec![test_account(profile)],
+                    passphrase_hash: None,
+                })
+                .unwrap();
+        }
+
+        let accounts = backend(None).load().unwrap().accounts;
+        assert_eq!(accounts.len(), 1);
+        assert_eq!(accounts[0].name, "github");
+        let accounts = backend(Some("index")).load().unwrap().accounts;
+        assert_eq!(accounts[0].name, "index");
+    }
+
+    #[test]
+    fn test_failed_write_leaves_original_file_intact() {
+        let temp_dir = tempfile::TempDir::new().unwrap();
+        let path = temp_dir.path().join("accounts.json");
+        fs::write(&path, "original").unwrap();
+
+        let result = write_file_atomically(&path, |file| {
+            file.write_all(b"partial")?;
+            Err(io::Error::other("disk full"))
+        });
+
+        assert!(result.is_err());
+        assert_eq!(fs::read_to_string(&path).unwrap(), "original");
+        assert_eq!(fs::read_dir(temp_dir.path()).unwrap().count(), 1);
+    }
+
+    #[test]
+    #[cfg(unix)]
+    fn test_saved_file_has_owner_only_permissions() {
+        use std::os::unix::fs::PermissionsExt;
+
+        let temp_dir = tempfile::TempDir::new().unwrap();
+        let path = temp_dir.path().join("accounts.json");
+        FileBackend::new(path.to_str().unwrap())
+            .save(&Storage::default())
+            .unwrap();
+
+        let mode = fs::metadata(&path).unwrap().permissions().mode();
+        assert_eq!(mode & 0o777, 0o600);
+    }
+
+    #[test]
+    fn test_lock_times_out_when_held_elsewhere() {
+        let temp_dir = tempfile::TempDir::new().unwrap();
+        let path = temp_dir.path().join("accounts.json");
+        let backend = FileBackend::new(path.to_str().unwrap());
+
+        let _held = backend.lock(true).unwrap();
+        let result = backend.lock_within(true, Duration::from_millis(100));
+
+        let err = result.err().expect("Lock should not be acquired");
+        assert!(err.to_string().contains("locked by another process"));
+    }
+}
@@ -0,0 +1,157 @@
+use std::env;
+use std::fs;
+use std::io::ErrorKind;
+use std::path::Path;
+
+use crossterm::style::Color;
+use serde::Deserialize;
+
+use crate::{AppError, profile};
+
+const THEME_FILE: &str = "theme.toml";
+
+/// Colors used by the dashboard.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Theme {
+    pub highlight_fg: Color,
+    pub highlight_bg: Color,
+    pub copied: Color,
+    pub progress: Color,
+    /// When false no colors or text attributes are written at all
+    pub styled: bool,
+}
+
+impl Default for Theme {
+    fn default() -> Self {
+        Self {
+            highlight_fg: Color::Black,
+            highlight_bg: Color::White,
+            copied: Color::Green,
+            progress: Color::Reset,
+            styled: true,
+        }
+    }
+}
+
+// Every field is optional so a theme can override just one color
+#[derive(Deserialize, Default)]
+#[serde(default, deny_unknown_fields)]
+struct ThemeFile {
+    highlight_fg: Option<String>,
+    highlight_bg: Option<String>,
+    copied: Option<String>,
+    progress: Option<String>,
+}
+
+impl Theme {
+    /// Theme with all styling turned off.
+    pub fn plain() -> Self {
+        Self {
+            styled: false,
+            ..Self::default()
+        }
+    }
+
+    /// Loads `file`, or else `theme.toml` from the config directory, which
+    /// doesn't have to exist. Styling is disabled by `no_color` or a
+    /// non-empty `NO_COLOR` variable, see https://no-color.org.
+    pub fn load(no_color: bool, file: Option<&Path>) -> Result<Self, AppError> {
+        if no_color || env::var_os("NO_COLOR").is_some_and(|value| !value.is_empty()) {
+            return Ok(Self::plain());
+        }
+
+        let path = match file {
+            Some(file) => file.to_path_buf(),
+            None => profile::config_dir()?.join(THEME_FILE),
+        };
+        let contents = match fs::read_to_string(&path) {
+            Ok(contents) => contents,
+            Err(e) if e.kind() == ErrorKind::NotFound && file.is_none() => {
+                return Ok(Self::default());
+            }
+            Err(e) => {
+                return Err(AppError::Storage(format!(
+                    "Failed to read {}: {}",
+                    path.display(),
+                    e
+                )));
+            }
+        };
+        Self::parse(&contents).map_err(|e| {
+            AppError::InvalidInput(format!("Invalid theme in {}: {}", path.display(), e))
+        })
+    }
+
+    fn parse(contents: &str) -> Result<Self, String> {
+        let file: ThemeFile = toml::from_str(contents).map_err(|e| e.message().to_string())?;
+        let mut theme = Self::default();
+        for (value, color) in [
+            (file.highlight_fg, &mut theme.highlight_fg),
+            (file.highlight_bg, &mut theme.highlight_bg),
+            (file.copied, &mut theme.copied),
+            (file.progress, &mut theme.progress),
+        ] {
+            if let Some(value) = value {
+                *color = parse_color(&value)?;
+            }
+        }
+        Ok(theme)
+    }
+}
+
+/// Accepts crossterm color names such as `dark_grey` (or `dark-grey`) and
+/// hex values like `#1e90ff`.
+fn parse_color(value: &str) -> Result<Color, String> {
+    if let Some(hex) = value.strip_prefix('#') {
+        let channel = |i: usize| {
+            hex.get(i..i + 2)
+                .and_then(|digits| u8::from_str_radix(digits, 16).ok())
+        };
+        if hex.len() == 6 {
+            if let (Some(r), Some(g), Some(b)) = (channel(0), channel(2), channel(4)) {
+                return Ok(Color::Rgb { r, g, b });
+            }
+        }
+    } else if let Ok(color) = Color::try_from(value.replace('-', "_").as_str()) {
+        return Ok(color);
+    }
+    Err(format!("unknown color '{}'", value))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_theme() {
+        let theme = Theme::parse(
+            r##"
+            highlight_fg = "white"
+            highlight_bg = "dark-blue"
+            progress = "#1e90ff"
+            "##,
+        )
+        .unwrap();
+
+        assert_eq!(theme.highlight_fg, Color::White);
+        assert_eq!(theme.highlight_bg, Color::DarkBlue);
+        assert_eq!(
+            theme.progress,
+            Color::Rgb {
+                r: 0x1e,
+                g: 0x90,
+                b: 0xff
+            }
+        );
+        // Colors that aren't set keep their defaults
+        assert_eq!(theme.copied, Theme::default().copied);
+        assert!(theme.styled);
+    }
+
+    #[test]
+    fn test_parse_theme_rejects_bad_values() {
+        assert!(Theme::parse(r#"copied = "chartreuse""#).is_err());
+        assert!(Theme::parse(r##"copied = "#12345""##).is_err());
+        assert!(Theme::parse(r#"background = "red""#).is_err());
+    }
+}
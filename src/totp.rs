@@ -1,20 +1,175 @@
 use base32::{Alphabet, decode};
 use hmac::{Hmac, Mac};
-use serde::{Deserialize, Serialize};
+use serde::{Deserialize, Deserializer, Serialize};
 use sha1::Sha1;
 use sha2::{Sha256, Sha512};
+use std::fmt;
+use std::ops::Deref;
 use std::time::Duration;
+use subtle::ConstantTimeEq;
+use zeroize::{Zeroize, ZeroizeOnDrop, Zeroizing};
 
 use super::AppError;
 
+/// Algorithm name for Steam Guard codes, which use HMAC-SHA1 but render the
+/// truncated value in a custom alphabet rather than as decimal digits.
+pub const STEAM_ALGORITHM: &str = "STEAM";
+
+/// Issuer given to accounts that don't name one.
+pub const DEFAULT_ISSUER: &str = "hotpot";
+
+/// Fewest digits a code can have. RFC 4226 requires at least six.
+pub const MIN_DIGITS: u32 = 6;
+/// Most digits a code can have; the truncated value is 31 bits, so more
+/// digits would only add leading zeros.
+pub const MAX_DIGITS: u32 = 10;
+
+/// Most time steps either side of now that a window of codes may cover.
+/// Ten steps is five minutes for the usual 30-second period, which is more
+/// than any clock that still works should drift.
+pub const MAX_WINDOW: u32 = 10;
+
+const STEAM_ALPHABET: &[u8] = b"23456789BCDFGHJKMNPQRTVWXY";
+const STEAM_CODE_LENGTH: usize = 5;
+
+/// A Base32 secret that is wiped from memory when dropped. It serializes as
+/// a plain string, so stored accounts look the same as before.
+#[derive(Serialize, Deserialize, Clone, Default, PartialEq, Eq, Zeroize, ZeroizeOnDrop)]
+#[serde(transparent)]
+pub struct Secret(String);
+
+impl Secret {
+    pub fn as_str(&self) -> &str {
+        &self.0
+    }
+}
+
+impl Deref for Secret {
+    type Target = str;
+
+    fn deref(&self) -> &str {
+        &self.0
+    }
+}
+
+impl From<String> for Secret {
+    fn from(secret: String) -> Self {
+        Self(secret)
+    }
+}
+
+impl From<&str> for Secret {
+    fn from(secret: &str) -> Self {
+        Self(secret.to_string())
+    }
+}
+
+impl PartialEq<&str> for Secret {
+    fn eq(&self, other: &&str) -> bool {
+        self.0 == *other
+    }
+}
+
+// Keeps secrets out of panic messages and debug logs
+impl fmt::Debug for Secret {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("Secret(..)")
+    }
+}
+
+/// How an account's secret is written down.
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, Default, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum SecretEncoding {
+    /// RFC4648 without padding, as otpauth URIs use
+    #[default]
+    Base32,
+    /// Hexadecimal, as in RFC 6238 and some older systems
+    Hex,
+}
+
+impl SecretEncoding {
+    fn is_base32(&self) -> bool {
+        *self == Self::Base32
+    }
+
+    /// Strips the formatting that sites commonly add to secrets, see
+    /// `normalize_secret` for Base32. Hex secrets may start with `0x`.
+    pub fn normalize(self, secret: &str) -> String {
+        match self {
+            Self::Base32 => normalize_secret(secret),
+            Self::Hex => {
+                let compact = secret
+                    .chars()
+                    .filter(|c| !c.is_whitespace())
+                    .collect::<String>()
+                    .to_ascii_lowercase();
+                match compact.strip_prefix("0x") {
+                    Some(digits) => digits.to_string(),
+                    None => compact,
+                }
+            }
+        }
+    }
+
+    /// Decodes a secret that `normalize` has already been applied to.
+    fn decode(self, normalized: &str) -> Option<Vec<u8>> {
+        match self {
+            Self::Base32 => decode(Alphabet::RFC4648 { padding: false }, normalized),
+            Self::Hex => {
+                if normalized.len() % 2 != 0 || !normalized.is_ascii() {
+                    return None;
+                }
+                (0..normalized.len())
+                    .step_by(2)
+                    .map(|i| u8::from_str_radix(&normalized[i..i + 2], 16).ok())
+                    .collect()
+            }
+        }
+    }
+
+    fn invalid_secret(self) -> AppError {
+        match self {
+            Self::Base32 => AppError::InvalidSecret,
+            Self::Hex => AppError::InvalidInput("Secret is not valid hex".to_string()),
+        }
+    }
+}
+
+impl fmt::Display for SecretEncoding {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            Self::Base32 => "Base32",
+            Self::Hex => "hex",
+        })
+    }
+}
+
+impl std::str::FromStr for SecretEncoding {
+    type Err = String;
+
+    fn from_str(value: &str) -> Result<Self, Self::Err> {
+        match value.to_ascii_lowercase().as_str() {
+            "base32" => Ok(Self::Base32),
+            "hex" => Ok(Self::Hex),
+            _ => Err("expected base32 or hex".to_string()),
+        }
+    }
+}
+
 #[derive(Serialize, Deserialize, Clone)]
 pub struct Account {
     pub name: String,
-    /// Base32 encoded secret key (RFC4648 without padding)
-    pub secret: String,
+    /// Secret key, written as `encoding` says
+    pub secret: Secret,
+    #[serde(default, skip_serializing_if = "SecretEncoding::is_base32")]
+    pub encoding: SecretEncoding,
     #[serde(default = "default_issuer")]
     pub issuer: String,
-    #[serde(default = "default_algorithm")]
+    #[serde(
+        default = "default_algorithm",
+        deserialize_with = "deserialize_algorithm"
+    )]
     pub algorithm: String,
     #[serde(default = "default_digits")]
     pub digits: u32,
@@ -22,16 +177,41 @@ pub struct Account {
     pub period: u32,
     #[serde(default = "default_epoch")]
     pub epoch: u64,
+    /// Free-form annotation; kept out of otpauth URIs
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub note: Option<String>,
+    /// Pinned to the top of the dashboard
+    #[serde(default, skip_serializing_if = "is_false")]
+    pub favorite: bool,
+    /// Seconds added to the system clock for this account only, to make up
+    /// for drift that can't be fixed on the machine
+    #[serde(default, skip_serializing_if = "is_zero")]
+    pub offset_seconds: i64,
+}
+
+fn is_false(value: &bool) -> bool {
+    !value
+}
+
+fn is_zero(value: &i64) -> bool {
+    *value == 0
 }
 
 fn default_issuer() -> String {
-    "hotpot".to_string()
+    DEFAULT_ISSUER.to_string()
 }
 
 fn default_algorithm() -> String {
     "SHA1".to_string()
 }
 
+// Normalizing on load lets generate_totp match names exactly
+fn deserialize_algorithm<'de, D: Deserializer<'de>>(deserializer: D) -> Result<String, D::Error> {
+    let name = String::deserialize(deserializer)?;
+    // Unknown names are kept so generate_totp can report them
+    Ok(normalize_algorithm(&name).map_or(name, str::to_string))
+}
+
 fn default_digits() -> u32 {
     6
 }
@@ -45,75 +225,406 @@ fn default_epoch() -> u64 {
 }
 
 impl Account {
+    /// Creates an account with the default settings. The secret is stored as
+    /// given; use `Account::builder` to have it checked.
     pub fn new(name: String, secret: String) -> Self {
-        Self {
-            name,
-            secret,
-            issuer: default_issuer(),
-            algorithm: default_algorithm(),
-            digits: default_digits(),
-            period: default_period(),
-            epoch: default_epoch(),
+        Self::builder(name, secret).account
+    }
+
+    /// Starts building an account with the default settings, which are
+    /// validated by `AccountBuilder::build`.
+    pub fn builder(name: impl Into<String>, secret: impl Into<String>) -> AccountBuilder {
+        AccountBuilder {
+            account: Self {
+                name: name.into(),
+                secret: Secret::from(secret.into()),
+                encoding: SecretEncoding::default(),
+                issuer: default_issuer(),
+                algorithm: default_algorithm(),
+                digits: default_digits(),
+                period: default_period(),
+                epoch: default_epoch(),
+                note: None,
+                favorite: false,
+                offset_seconds: 0,
+            },
         }
     }
 
+    /// The `otpauth://totp/` URI for this account, as put in QR codes. The
+    /// issuer, name and parameter values are percent-encoded, so characters
+    /// such as `&`, `#` or `:` in them survive a round trip.
     pub fn generate_uri(&self) -> String {
-        let label = format!("{}:{}", self.issuer, self.name);
+        let label = format!(
+            "{}:{}",
+            urlencoding::encode(&self.issuer),
+            urlencoding::encode(&self.name)
+        );
+        let secret = self.base32_secret();
         let digits = self.digits.to_string();
         let period = self.period.to_string();
         let params = [
-            ("secret", &self.secret),
+            ("secret", secret.as_str()),
             ("issuer", &self.issuer),
             ("algorithm", &self.algorithm),
             ("digits", &digits),
             ("period", &period),
         ];
 
-        let query = params
+        let mut query = params
             .iter()
-            .map(|(k, v)| format!("{k}={v}"))
+            .map(|(k, v)| format!("{k}={}", urlencoding::encode(v)))
             .collect::<Vec<_>>()
             .join("&");
+        if self.algorithm == STEAM_ALGORITHM {
+            query.push_str("&encoder=steam");
+        }
 
         format!("otpauth://totp/{label}?{query}")
     }
+
+    /// The secret in Base32, as otpauth URIs and most authenticator apps
+    /// expect it, whatever `encoding` it is stored in.
+    pub fn base32_secret(&self) -> Zeroizing<String> {
+        match decode_secret(&self.secret, self.encoding) {
+            Ok(key) if !self.encoding.is_base32() => {
+                Zeroizing::new(base32::encode(Alphabet::RFC4648 { padding: false }, &key))
+            }
+            // Invalid secrets are left for generate_totp to report
+            _ => Zeroizing::new(self.secret.as_str().to_string()),
+        }
+    }
+
+    /// Parses an `otpauth://totp/` URI, as found in QR codes or produced by
+    /// `generate_uri`. Settings the URI leaves out get the usual defaults.
+    pub fn from_otpauth_uri(uri: &str) -> Result<Account, AppError> {
+        if !uri.starts_with("otpauth://totp/") {
+            return Err(AppError::InvalidInput(
+                "Invalid otpauth URI format".to_string(),
+            ));
+        }
+
+        let url = url::Url::parse(uri)
+            .map_err(|e| AppError::InvalidInput(format!("Failed to parse URI: {}", e)))?;
+        let (label_issuer, account_name) = parse_label(&url)?;
+
+        // Start from the defaults and override with whatever the URI specifies
+        let mut builder = Account::builder(account_name, String::new());
+        let mut has_secret = false;
+        let mut has_issuer = false;
+
+        for (key, value) in url.query_pairs() {
+            builder = match key.as_ref() {
+                "secret" => {
+                    has_secret = !value.is_empty();
+                    builder.secret(value)
+                }
+                "issuer" => {
+                    has_issuer = true;
+                    builder.issuer(value)
+                }
+                "algorithm" => builder.algorithm(&value),
+                "digits" => builder.digits(value.parse().map_err(|_| {
+                    AppError::InvalidInput(format!("Invalid digits '{}' in otpauth URI", value))
+                })?),
+                "period" => builder.period(value.parse().map_err(|_| {
+                    AppError::InvalidInput(format!("Invalid period '{}' in otpauth URI", value))
+                })?),
+                // Steam Guard URIs mark themselves with a non-standard encoder parameter
+                "encoder" if value.eq_ignore_ascii_case("steam") => {
+                    builder.algorithm(STEAM_ALGORITHM)
+                }
+                _ => builder,
+            };
+        }
+
+        if !has_secret {
+            return Err(AppError::InvalidInput(
+                "No secret found in otpauth URI".to_string(),
+            ));
+        }
+
+        // The issuer parameter wins over the label prefix, as the Key Uri
+        // Format recommends
+        if !has_issuer {
+            if let Some(label) = label_issuer.filter(|i| !i.is_empty()) {
+                builder = builder.issuer(label);
+            }
+        }
+        builder.build()
+    }
+
+    /// The issuer parameter and label prefix of otpauth URI `uri`, in that
+    /// order, when it has both and they disagree. `from_otpauth_uri` uses
+    /// the parameter, but a mismatch usually means a malformed URI, so
+    /// callers may want to warn about it.
+    pub fn label_issuer_mismatch(uri: &str) -> Option<(String, String)> {
+        let url = url::Url::parse(uri).ok()?;
+        let label_issuer = parse_label(&url).ok()?.0.filter(|i| !i.is_empty())?;
+        let param_issuer = url
+            .query_pairs()
+            .filter(|(key, _)| key == "issuer")
+            .last()?
+            .1
+            .into_owned();
+        (param_issuer != label_issuer).then_some((param_issuer, label_issuer))
+    }
+
+    /// Renders a value returned by `generate_totp` for display, zero-padding
+    /// numeric codes to `digits` and mapping Steam codes into Steam's alphabet.
+    pub fn format_code(&self, code: u32) -> String {
+        if self.algorithm == STEAM_ALGORITHM {
+            let mut value = code as usize;
+            (0..STEAM_CODE_LENGTH)
+                .map(|_| {
+                    let c = STEAM_ALPHABET[value % STEAM_ALPHABET.len()] as char;
+                    value /= STEAM_ALPHABET.len();
+                    c
+                })
+                .collect()
+        } else {
+            format!("{:0width$}", code, width = self.digits as usize)
+        }
+    }
+
+    /// Checks the settings `generate_totp` depends on, other than the secret.
+    pub fn validate(&self) -> Result<(), AppError> {
+        if self.period == 0 {
+            return Err(AppError::InvalidInput(
+                "period must be greater than zero".to_string(),
+            ));
+        }
+        // Steam codes have a fixed length, so their digits are ignored
+        if self.algorithm != STEAM_ALGORITHM && !(MIN_DIGITS..=MAX_DIGITS).contains(&self.digits) {
+            return Err(AppError::InvalidInput(format!(
+                "digits must be between {} and {}, not {}",
+                MIN_DIGITS, MAX_DIGITS, self.digits
+            )));
+        }
+        Ok(())
+    }
+
+    /// `duration` since the Unix epoch with `offset_seconds` applied, i.e. the
+    /// time as the account's server sees it. Never before the Unix epoch.
+    pub fn adjusted_time(&self, duration: Duration) -> Duration {
+        let offset = Duration::from_secs(self.offset_seconds.unsigned_abs());
+        if self.offset_seconds >= 0 {
+            duration.saturating_add(offset)
+        } else {
+            duration.saturating_sub(offset)
+        }
+    }
+
+    /// The time step counter for `duration`; codes only change when it does.
+    /// Returns 0 for accounts with a zero period, which cannot produce codes.
+    pub fn time_step(&self, duration: Duration) -> u64 {
+        self.adjusted_time(duration)
+            .as_secs()
+            .saturating_sub(self.epoch)
+            .checked_div(u64::from(self.period))
+            .unwrap_or(0)
+    }
+
+    /// Unix time by the system clock at which time step `step` begins.
+    pub fn step_start(&self, step: u64) -> u64 {
+        step.saturating_mul(u64::from(self.period))
+            .saturating_add(self.epoch)
+            .saturating_add_signed(self.offset_seconds.saturating_neg())
+    }
+
+    /// Seconds until the code for the time step containing `duration` expires.
+    /// Returns 0 for accounts with a zero period, which cannot produce codes.
+    pub fn remaining_seconds(&self, duration: Duration) -> u64 {
+        let period = u64::from(self.period);
+        self.adjusted_time(duration)
+            .as_secs()
+            .saturating_sub(self.epoch)
+            .checked_rem(period)
+            .map_or(0, |elapsed| period - elapsed)
+    }
+}
+
+/// Builds an `Account` with non-default settings, see `Account::builder`.
+#[derive(Clone)]
+pub struct AccountBuilder {
+    account: Account,
+}
+
+impl AccountBuilder {
+    pub fn secret(mut self, secret: impl Into<String>) -> Self {
+        self.account.secret = Secret::from(secret.into());
+        self
+    }
+
+    pub fn encoding(mut self, encoding: SecretEncoding) -> Self {
+        self.account.encoding = encoding;
+        self
+    }
+
+    pub fn issuer(mut self, issuer: impl Into<String>) -> Self {
+        self.account.issuer = issuer.into();
+        self
+    }
+
+    /// Any spelling `normalize_algorithm` understands, e.g. `sha-256`.
+    pub fn algorithm(mut self, algorithm: &str) -> Self {
+        self.account.algorithm = algorithm.to_string();
+        self
+    }
+
+    pub fn digits(mut self, digits: u32) -> Self {
+        self.account.digits = digits;
+        self
+    }
+
+    pub fn period(mut self, period: u32) -> Self {
+        self.account.period = period;
+        self
+    }
+
+    /// Unix time codes are counted from (T0).
+    pub fn epoch(mut self, epoch: u64) -> Self {
+        self.account.epoch = epoch;
+        self
+    }
+
+    pub fn note(mut self, note: impl Into<String>) -> Self {
+        self.account.note = Some(note.into());
+        self
+    }
+
+    /// Normalizes the secret and algorithm and checks every setting, so the
+    /// account is ready to generate codes.
+    pub fn build(self) -> Result<Account, AppError> {
+        let mut account = self.account;
+        account.secret = validate_secret(&account.secret, account.encoding)?.into();
+        account.algorithm = normalize_algorithm(&account.algorithm)
+            .ok_or_else(|| {
+                AppError::InvalidInput(format!("Unsupported algorithm '{}'", account.algorithm))
+            })?
+            .to_string();
+        // Steam codes always have the same length
+        if account.algorithm == STEAM_ALGORITHM {
+            account.digits = STEAM_CODE_LENGTH as u32;
+        }
+        account.validate()?;
+        Ok(account)
+    }
+}
+
+/// A generated code along with what's needed to display it.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct TotpCode {
+    /// The value returned by `generate_totp`
+    pub code: u32,
+    /// Length of `formatted`; five for Steam codes
+    pub digits: u32,
+    /// The code as shown to users, see `Account::format_code`
+    pub formatted: String,
+    /// Time step counter the code was generated for
+    pub step: u64,
+    /// Time since the Unix epoch at which the code expires
+    pub valid_until: Duration,
 }
 
+impl TotpCode {
+    /// Whole seconds from `now` until the code expires.
+    pub fn remaining_seconds(&self, now: Duration) -> u64 {
+        self.valid_until.as_secs().saturating_sub(now.as_secs())
+    }
+
+    /// `formatted` split in two for reading aloud or typing, e.g. `123 456`
+    /// or `1234 5678`, or `formatted` itself when `grouped` is false. Steam
+    /// codes are never split. Only for display: copy `formatted` instead.
+    pub fn display(&self, grouped: bool) -> String {
+        if !grouped || !self.formatted.bytes().all(|b| b.is_ascii_digit()) {
+            return self.formatted.clone();
+        }
+        let (first, second) = self.formatted.split_at(self.formatted.len() / 2);
+        format!("{} {}", first, second)
+    }
+}
+
+/// Generates the TOTP value for `account` at `duration` since the Unix epoch.
+///
+/// For Steam accounts the untruncated 31-bit value is returned; use
+/// `Account::format_code` to turn it into the displayed code, or call
+/// `generate_totp_full` to get both.
 pub fn generate_totp(account: &Account, duration: Duration) -> Result<u32, AppError> {
-    let secret_bytes = match decode(Alphabet::RFC4648 { padding: false }, &account.secret) {
-        Some(bytes) => bytes,
-        None => return Err(AppError::new("Bytes could not be decoded")),
-    };
+    generate_totp_full(account, duration).map(|totp| totp.code)
+}
 
+/// Generates the code for `account` at `duration` since the Unix epoch,
+/// together with its display form and expiry.
+pub fn generate_totp_full(account: &Account, duration: Duration) -> Result<TotpCode, AppError> {
+    generate_totp_with_key(
+        account,
+        &decode_secret(&account.secret, account.encoding)?,
+        duration,
+    )
+}
+
+/// Decodes a secret into the HMAC key that `generate_totp_with_key` takes,
+/// for callers that generate many codes from the same account.
+pub fn decode_secret(
+    secret: &str,
+    encoding: SecretEncoding,
+) -> Result<Zeroizing<Vec<u8>>, AppError> {
+    // Secrets are normalized when added, but stored files may predate that
+    // or have been edited by hand
+    let secret = Zeroizing::new(encoding.normalize(secret));
+    encoding
+        .decode(&secret)
+        .map(Zeroizing::new)
+        .ok_or_else(|| encoding.invalid_secret())
+}
+
+/// Like `generate_totp_full`, but with the secret already decoded by
+/// `decode_secret`. `account.secret` is ignored.
+pub fn generate_totp_with_key(
+    account: &Account,
+    secret_bytes: &[u8],
+    duration: Duration,
+) -> Result<TotpCode, AppError> {
     // T = (Current Unix time - T0) / X, where:
     // - Current Unix time = duration.as_secs()
     // - T0 = account.epoch (default 0 for Unix epoch)
     // - X = account.period (default 30 seconds)
-    let counter = (duration.as_secs().saturating_sub(account.epoch)) / u64::from(account.period);
+    generate_totp_for_step(account, secret_bytes, account.time_step(duration))
+}
+
+/// Like `generate_totp_with_key`, but for the time step counter `step` rather
+/// than a point in time, e.g. to show the codes either side of the current one.
+pub fn generate_totp_for_step(
+    account: &Account,
+    secret_bytes: &[u8],
+    step: u64,
+) -> Result<TotpCode, AppError> {
+    account.validate()?;
 
     // Convert counter to exactly 8 bytes big-endian per RFC 6238
-    let counter_bytes = counter.to_be_bytes();
+    let counter_bytes = step.to_be_bytes();
 
     let result = match account.algorithm.as_str() {
-        "SHA1" => {
+        "SHA1" | STEAM_ALGORITHM => {
             let mut mac =
-                Hmac::<Sha1>::new_from_slice(&secret_bytes).expect("HMAC can take key of any size");
+                Hmac::<Sha1>::new_from_slice(secret_bytes).expect("HMAC can take key of any size");
             mac.update(&counter_bytes);
             mac.finalize().into_bytes().to_vec()
         }
         "SHA256" => {
-            let mut mac = Hmac::<Sha256>::new_from_slice(&secret_bytes)
+            let mut mac = Hmac::<Sha256>::new_from_slice(secret_bytes)
                 .expect("HMAC can take key of any size");
             mac.update(&counter_bytes);
             mac.finalize().into_bytes().to_vec()
         }
         "SHA512" => {
-            let mut mac = Hmac::<Sha512>::new_from_slice(&secret_bytes)
+            let mut mac = Hmac::<Sha512>::new_from_slice(secret_bytes)
                 .expect("HMAC can take key of any size");
             mac.update(&counter_bytes);
             mac.finalize().into_bytes().to_vec()
         }
-        _ => return Err(AppError::new("Unsupported algorithm")),
+        _ => return Err(AppError::InvalidInput("Unsupported algorithm".to_string())),
     };
 
     // Use last byte of hash to determine offset
@@ -124,8 +635,117 @@ pub fn generate_totp(account: &Account, duration: Duration) -> Result<u32, AppEr
         | ((u32::from(result[offset + 2]) & 0xff) << 8)
         | (u32::from(result[offset + 3]) & 0xff);
 
-    let modulus = 10u32.pow(account.digits);
-    Ok(binary % modulus)
+    let (code, digits) = if account.algorithm == STEAM_ALGORITHM {
+        (binary, STEAM_CODE_LENGTH as u32)
+    } else {
+        // 10^10 overflows u32, so reduce in u64; the result always fits
+        let modulus = 10u64.pow(account.digits);
+        ((u64::from(binary) % modulus) as u32, account.digits)
+    };
+
+    Ok(TotpCode {
+        code,
+        digits,
+        formatted: account.format_code(code),
+        step,
+        valid_until: Duration::from_secs(account.step_start(step.saturating_add(1))),
+    })
+}
+
+/// Checks `code` against the codes for the time step containing `now` and up
+/// to `window` steps either side of it. A `window` above [`MAX_WINDOW`] is
+/// treated as `MAX_WINDOW`, as each step costs an HMAC.
+///
+/// Returns the step offset that matched, preferring the closest one, or
+/// `None` if no step in the window produces `code`.
+pub fn verify_totp(account: &Account, code: &str, now: Duration, window: u32) -> Option<i64> {
+    let code = code.trim();
+    let key = decode_secret(&account.secret, account.encoding).ok()?;
+    let current = account.time_step(now);
+    let window = i64::from(window.min(MAX_WINDOW));
+
+    let offsets = std::iter::once(0).chain((1..=window).flat_map(|step| [-step, step]));
+    for offset in offsets {
+        let Some(step) = current.checked_add_signed(offset) else {
+            continue;
+        };
+        if let Ok(totp) = generate_totp_for_step(account, &key, step) {
+            // Compared in constant time, so timing doesn't reveal how much
+            // of a guess was right
+            if bool::from(totp.formatted.as_bytes().ct_eq(code.as_bytes())) {
+                return Some(offset);
+            }
+        }
+    }
+    None
+}
+
+/// Splits an otpauth URI's label into its issuer prefix, if any, and the
+/// account name.
+fn parse_label(url: &url::Url) -> Result<(Option<String>, String), AppError> {
+    // The label is `issuer:account` or just `account`, separated by a
+    // literal or encoded colon. A literal one is looked for first, as the
+    // parts may hold encoded colons of their own
+    let path = url.path().trim_start_matches('/');
+    let decode = |part| {
+        urlencoding::decode(part)
+            .map_err(|e| AppError::InvalidInput(format!("Invalid label in otpauth URI: {}", e)))
+    };
+    let (label_issuer, account_name) = match path.rsplit_once(':') {
+        Some((issuer, name)) => (Some(decode(issuer)?.into_owned()), decode(name)?),
+        None => {
+            let label = decode(path)?;
+            match label.rsplit_once(':') {
+                Some((issuer, name)) => (Some(issuer.to_string()), name.to_string().into()),
+                None => (None, label),
+            }
+        }
+    };
+    Ok((
+        label_issuer.map(|issuer| issuer.trim().to_string()),
+        account_name.trim_start().to_string(),
+    ))
+}
+
+/// Removes whitespace and `=` padding and uppercases a Base32 secret, since
+/// many sites display secrets in lowercase or in space-separated groups, and
+/// some exporters pad them to a multiple of eight characters.
+pub fn normalize_secret(secret: &str) -> String {
+    let mut normalized = secret
+        .chars()
+        .filter(|c| !c.is_whitespace())
+        .collect::<String>()
+        .to_ascii_uppercase();
+    let unpadded = normalized.trim_end_matches('=').len();
+    normalized.truncate(unpadded);
+    normalized
+}
+
+/// Maps an algorithm name such as `sha256`, `SHA-256` or `HmacSHA256` to the
+/// canonical name stored on accounts, or `None` if it isn't supported.
+pub fn normalize_algorithm(name: &str) -> Option<&'static str> {
+    let compact = name
+        .chars()
+        .filter(|c| !matches!(c, '-' | '_' | ' '))
+        .collect::<String>()
+        .to_ascii_uppercase();
+    match compact.strip_prefix("HMAC").unwrap_or(&compact) {
+        "SHA1" => Some("SHA1"),
+        "SHA256" => Some("SHA256"),
+        "SHA512" => Some("SHA512"),
+        STEAM_ALGORITHM => Some(STEAM_ALGORITHM),
+        _ => None,
+    }
+}
+
+/// Normalizes a secret and checks that it decodes as `encoding`.
+pub fn validate_secret(secret: &str, encoding: SecretEncoding) -> Result<String, AppError> {
+    let mut normalized = encoding.normalize(secret);
+    if normalized.is_empty() || encoding.decode(&normalized).map(Zeroizing::new).is_none() {
+        normalized.zeroize();
+        return Err(encoding.invalid_secret());
+    }
+    Ok(normalized)
 }
 
 pub fn generate_otpauth_uri(name: &str, secret: &str) -> String {
@@ -158,12 +778,16 @@ mod tests {
     fn create_test_account(secret: &str) -> Account {
         Account {
             name: "test".to_string(),
-            secret: ascii_to_base32(secret),
+            secret: ascii_to_base32(secret).into(),
+            encoding: SecretEncoding::Base32,
             issuer: default_issuer(),
             algorithm: default_algorithm(),
             digits: 8, // RFC test vectors use 8 digits
             period: 30,
             epoch: default_epoch(),
+            note: None,
+            favorite: false,
+            offset_seconds: 0,
         }
     }
 
@@ -299,12 +923,563 @@ mod tests {
     #[test]
     fn test_invalid_secret() {
         let mut account = create_test_account(TEST_SECRET_SHA1);
-        account.secret = "invalid base32".to_string();
+        account.secret = "invalid base32!".into();
 
         let duration = Duration::from_secs(59);
         assert!(generate_totp(&account, duration).is_err());
     }
 
+    #[test]
+    fn test_verify_totp() {
+        let account = create_test_account(TEST_SECRET_SHA1);
+        let now = Duration::from_secs(1111111109);
+
+        assert_eq!(verify_totp(&account, "07081804", now, 1), Some(0));
+        // 1111111111 is in the following step
+        assert_eq!(verify_totp(&account, "14050471", now, 1), Some(1));
+        assert_eq!(
+            verify_totp(&account, "07081804", now + Duration::from_secs(30), 1),
+            Some(-1)
+        );
+        assert_eq!(
+            verify_totp(&account, "07081804", now + Duration::from_secs(30), 0),
+            None
+        );
+        assert_eq!(verify_totp(&account, "00000000", now, 1), None);
+
+        // Huge windows are cut down to MAX_WINDOW rather than run for ages
+        let later = |steps: u64| now + Duration::from_secs(30 * steps);
+        assert_eq!(
+            verify_totp(&account, "07081804", later(10), u32::MAX),
+            Some(-10)
+        );
+        assert_eq!(verify_totp(&account, "07081804", later(11), u32::MAX), None);
+    }
+
+    #[test]
+    fn test_offset_seconds() {
+        let mut account = create_test_account(TEST_SECRET_SHA1);
+        // The clock is 50 seconds slow, so 9 is really 59
+        account.offset_seconds = 50;
+        let totp = generate_totp_full(&account, Duration::from_secs(9)).unwrap();
+        assert_eq!(totp.formatted, "94287082");
+        // Expiry is by the system clock: the step ends at 60, which is 10 here
+        assert_eq!(totp.valid_until, Duration::from_secs(10));
+        assert_eq!(totp.remaining_seconds(Duration::from_secs(9)), 1);
+        assert_eq!(account.remaining_seconds(Duration::from_secs(9)), 1);
+
+        // A clock that is fast can't move the time before the Unix epoch
+        account.offset_seconds = -100;
+        assert_eq!(
+            account.adjusted_time(Duration::from_secs(59)),
+            Duration::ZERO
+        );
+        assert_eq!(account.time_step(Duration::from_secs(159)), 1);
+        assert_eq!(account.step_start(1), 130);
+        assert!(generate_totp(&account, Duration::from_secs(5)).is_ok());
+    }
+
+    #[test]
+    fn test_generate_totp_for_step() {
+        let account = create_test_account(TEST_SECRET_SHA1);
+        let key = decode_secret(&account.secret, account.encoding).unwrap();
+        let now = Duration::from_secs(1111111109);
+        let step = account.time_step(now);
+
+        let totp = generate_totp_for_step(&account, &key, step).unwrap();
+        assert_eq!(totp, generate_totp_with_key(&account, &key, now).unwrap());
+        assert_eq!(totp.formatted, "07081804");
+        assert_eq!(totp.valid_until, Duration::from_secs(1111111110));
+        assert_eq!(account.step_start(step), 1111111080);
+
+        let next = generate_totp_for_step(&account, &key, step + 1).unwrap();
+        assert_eq!(next.formatted, "14050471");
+    }
+
+    #[test]
+    fn test_note_is_not_in_uri() {
+        let mut account = Account::new("github".to_string(), "JBSWY3DPEHPK3PXP".to_string());
+        account.note = Some("backup codes in 1Password".to_string());
+
+        let uri = account.generate_uri();
+        assert!(!uri.contains("note"));
+        assert!(!uri.contains("1Password"));
+    }
+
+    #[test]
+    fn test_ten_digit_codes() {
+        let mut account = create_test_account(TEST_SECRET_SHA1);
+        account.digits = 10;
+
+        // The 31-bit value is shorter than 10 digits, so it is used as is
+        let code = generate_totp(&account, Duration::from_secs(59)).unwrap();
+        assert_eq!(account.format_code(code), "1094287082");
+    }
+
+    #[test]
+    fn test_zero_period_is_an_error() {
+        let mut account = create_test_account(TEST_SECRET_SHA1);
+        account.period = 0;
+
+        let err = generate_totp(&account, Duration::from_secs(59)).unwrap_err();
+        assert_eq!(err.to_string(), "period must be greater than zero");
+        assert_eq!(account.remaining_seconds(Duration::from_secs(59)), 0);
+        assert_eq!(
+            verify_totp(&account, "94287082", Duration::from_secs(59), 1),
+            None
+        );
+    }
+
+    #[test]
+    fn test_validate_secret() {
+        assert_eq!(
+            validate_secret(" jbsw y3dp ehpk 3pxp ", SecretEncoding::Base32).unwrap(),
+            "JBSWY3DPEHPK3PXP"
+        );
+        assert_eq!(
+            validate_secret("jbswy3dpee======", SecretEncoding::Base32).unwrap(),
+            "JBSWY3DPEE"
+        );
+        assert!(validate_secret("not base32!", SecretEncoding::Base32).is_err());
+        assert!(validate_secret("   ", SecretEncoding::Base32).is_err());
+        assert!(validate_secret("========", SecretEncoding::Base32).is_err());
+    }
+
+    #[test]
+    fn test_generate_totp_normalizes_secret() {
+        let time = Duration::from_secs(59);
+        let canonical = Account::new("test".to_string(), "JBSWY3DPEHPK3PXP".to_string());
+        let expected = generate_totp(&canonical, time).unwrap();
+
+        for secret in [
+            "jbswy3dpehpk3pxp",
+            "jbsw y3dp ehpk 3pxp",
+            " JBSW\tY3DP\nEHPK 3PXP ",
+        ] {
+            let account = Account::new("test".to_string(), secret.to_string());
+            assert_eq!(
+                generate_totp(&account, time).unwrap(),
+                expected,
+                "Secret '{}' should produce the canonical code",
+                secret
+            );
+        }
+    }
+
+    #[test]
+    fn test_hex_secret() {
+        // The RFC 6238 SHA1 secret, as the RFC's reference code writes it
+        let hex = "0x3132333435363738393031323334353637383930";
+        let account = Account::builder("test", hex)
+            .encoding(SecretEncoding::Hex)
+            .digits(8)
+            .build()
+            .unwrap();
+        assert_eq!(account.secret, "3132333435363738393031323334353637383930");
+        assert_eq!(
+            generate_totp(&account, Duration::from_secs(59)).unwrap(),
+            94287082
+        );
+
+        // URIs always carry the Base32 form
+        assert_eq!(
+            account.base32_secret().as_str(),
+            ascii_to_base32(TEST_SECRET_SHA1)
+        );
+        let parsed = Account::from_otpauth_uri(&account.generate_uri()).unwrap();
+        assert_eq!(
+            generate_totp(&parsed, Duration::from_secs(59)).unwrap(),
+            94287082
+        );
+
+        for invalid in ["313", "31zz", ""] {
+            assert!(
+                validate_secret(invalid, SecretEncoding::Hex).is_err(),
+                "{}",
+                invalid
+            );
+        }
+    }
+
+    #[test]
+    fn test_encoding_defaults_to_base32() {
+        let account: Account =
+            serde_json::from_str(r#"{"name": "a", "secret": "JBSWY3DPEHPK3PXP"}"#).unwrap();
+        assert_eq!(account.encoding, SecretEncoding::Base32);
+        assert!(
+            serde_json::to_value(&account)
+                .unwrap()
+                .get("encoding")
+                .is_none()
+        );
+
+        let account: Account =
+            serde_json::from_str(r#"{"name": "a", "secret": "3132333435", "encoding": "hex"}"#)
+                .unwrap();
+        assert_eq!(account.encoding, SecretEncoding::Hex);
+        assert_eq!(serde_json::to_value(&account).unwrap()["encoding"], "hex");
+    }
+
+    #[test]
+    fn test_generate_totp_accepts_padded_secret() {
+        let time = Duration::from_secs(59);
+        let canonical = Account::new("test".to_string(), "JBSWY3DPEE".to_string());
+        let expected = generate_totp(&canonical, time).unwrap();
+
+        for secret in [
+            "JBSWY3DPEE======",
+            "jbswy3dpee======",
+            "jbsw y3dp ee== ====",
+        ] {
+            let account = Account::new("test".to_string(), secret.to_string());
+            assert_eq!(
+                generate_totp(&account, time).unwrap(),
+                expected,
+                "{}",
+                secret
+            );
+        }
+    }
+
+    #[test]
+    fn test_normalize_algorithm() {
+        for name in ["SHA256", "sha256", "SHA-256", "sha_256", "HmacSHA256"] {
+            assert_eq!(normalize_algorithm(name), Some("SHA256"), "{}", name);
+        }
+        assert_eq!(normalize_algorithm("sha-1"), Some("SHA1"));
+        assert_eq!(normalize_algorithm("Sha512"), Some("SHA512"));
+        assert_eq!(normalize_algorithm("steam"), Some(STEAM_ALGORITHM));
+        assert_eq!(normalize_algorithm("MD5"), None);
+    }
+
+    #[test]
+    fn test_algorithm_normalized_on_load() {
+        let account: Account = serde_json::from_str(
+            r#"{"name": "a", "secret": "JBSWY3DPEHPK3PXP", "algorithm": "sha-256"}"#,
+        )
+        .unwrap();
+        assert_eq!(account.algorithm, "SHA256");
+    }
+
+    #[test]
+    fn test_favorite_defaults_to_unpinned() {
+        let account: Account =
+            serde_json::from_str(r#"{"name": "a", "secret": "JBSWY3DPEHPK3PXP"}"#).unwrap();
+        assert!(!account.favorite);
+        let json = serde_json::to_value(&account).unwrap();
+        assert!(json.get("favorite").is_none());
+    }
+
+    #[test]
+    fn test_digits_range() {
+        let mut account = create_test_account(TEST_SECRET_SHA1);
+        let time = Duration::from_secs(59);
+
+        account.digits = 0;
+        assert!(matches!(
+            generate_totp(&account, time),
+            Err(AppError::InvalidInput(_))
+        ));
+        assert!(account.validate().is_err());
+        account.digits = 11;
+        assert!(generate_totp(&account, time).is_err());
+
+        // RFC 6238 SHA1 vector at t=59, truncated to 9 and 10 digits
+        account.digits = 9;
+        assert_eq!(
+            account.format_code(generate_totp(&account, time).unwrap()),
+            "094287082"
+        );
+        account.digits = 10;
+        assert_eq!(
+            account.format_code(generate_totp(&account, time).unwrap()),
+            "1094287082"
+        );
+        assert!(account.validate().is_ok());
+
+        // Steam codes ignore digits
+        account.algorithm = STEAM_ALGORITHM.to_string();
+        account.digits = 5;
+        assert!(generate_totp(&account, time).is_ok());
+    }
+
+    #[test]
+    fn test_remaining_seconds() {
+        let mut account = create_test_account(TEST_SECRET_SHA1);
+        assert_eq!(account.remaining_seconds(Duration::from_secs(59)), 1);
+        assert_eq!(account.remaining_seconds(Duration::from_secs(60)), 30);
+
+        account.period = 60;
+        assert_eq!(account.remaining_seconds(Duration::from_secs(59)), 1);
+        assert_eq!(account.remaining_seconds(Duration::from_secs(61)), 59);
+    }
+
+    #[test]
+    fn test_steam_code() {
+        let mut account = create_test_account(TEST_SECRET_SHA1);
+        account.secret = "JBSWY3DPEHPK3PXP".into();
+        account.algorithm = STEAM_ALGORITHM.to_string();
+        account.digits = 5;
+
+        let code = generate_totp(&account, Duration::from_secs(59)).unwrap();
+        assert_eq!(account.format_code(code), "2YXGV");
+
+        let code = generate_totp(&account, Duration::from_secs(1111111109)).unwrap();
+        assert_eq!(account.format_code(code), "CWDGV");
+    }
+
+    #[test]
+    fn test_format_code_zero_pads() {
+        let account = create_test_account(TEST_SECRET_SHA1);
+        assert_eq!(account.format_code(7081804), "07081804");
+    }
+
+    #[test]
+    fn test_generate_totp_full() {
+        let account = create_test_account(TEST_SECRET_SHA1);
+        let now = Duration::from_secs(1111111109);
+        let totp = generate_totp_full(&account, now).unwrap();
+
+        assert_eq!(totp.code, 7081804);
+        assert_eq!(totp.code, generate_totp(&account, now).unwrap());
+        assert_eq!(totp.digits, 8);
+        assert_eq!(totp.formatted, "07081804");
+        assert_eq!(totp.display(true), "0708 1804");
+        assert_eq!(totp.display(false), "07081804");
+        assert_eq!(totp.step, 1111111109 / 30);
+        assert_eq!(totp.valid_until, Duration::from_secs(1111111110));
+        assert_eq!(totp.remaining_seconds(now), account.remaining_seconds(now));
+
+        let steam = Account {
+            algorithm: STEAM_ALGORITHM.to_string(),
+            ..account
+        };
+        let totp = generate_totp_full(&steam, now).unwrap();
+        assert_eq!(totp.digits, 5);
+        assert_eq!(totp.formatted.len(), 5);
+        assert_eq!(totp.display(true), totp.formatted);
+    }
+
+    #[test]
+    fn test_from_otpauth_uri_with_custom_parameters() {
+        let uri = format!(
+            "otpauth://totp/Example:alice?secret={}&issuer=Example&algorithm=SHA256&digits=8&period=60",
+            ascii_to_base32(TEST_SECRET_SHA256)
+        );
+        let account = Account::from_otpauth_uri(&uri).unwrap();
+
+        assert_eq!(account.name, "alice");
+        assert_eq!(account.issuer, "Example");
+        assert_eq!(account.algorithm, "SHA256");
+        assert_eq!(account.digits, 8);
+        assert_eq!(account.period, 60);
+
+        // With a 60s period, t=119 is counter 1, matching the RFC vector for t=59
+        let code = generate_totp(&account, Duration::from_secs(119)).unwrap();
+        assert_eq!(code, 46119246);
+    }
+
+    #[test]
+    fn test_from_otpauth_uri_defaults() {
+        let account =
+            Account::from_otpauth_uri("otpauth://totp/github?secret=JBSWY3DPEHPK3PXP").unwrap();
+
+        assert_eq!(account.name, "github");
+        assert_eq!(account.secret, "JBSWY3DPEHPK3PXP");
+        // Without an issuer the account gets the same one as `hotpot add`
+        assert_eq!(account.issuer, DEFAULT_ISSUER);
+        assert_eq!(account.algorithm, "SHA1");
+        assert_eq!(account.digits, 6);
+        assert_eq!(account.period, 30);
+    }
+
+    #[test]
+    fn test_from_otpauth_uri_steam_encoder() {
+        let account = Account::from_otpauth_uri(
+            "otpauth://totp/Steam:gamer?secret=JBSWY3DPEHPK3PXP&issuer=Steam&encoder=steam",
+        )
+        .unwrap();
+
+        assert_eq!(account.algorithm, STEAM_ALGORITHM);
+        assert_eq!(account.digits, 5);
+        let code = generate_totp(&account, Duration::from_secs(59)).unwrap();
+        assert_eq!(account.format_code(code), "2YXGV");
+    }
+
+    #[test]
+    fn test_from_otpauth_uri_algorithm_aliases() {
+        for (value, expected) in [
+            ("sha256", "SHA256"),
+            ("SHA-1", "SHA1"),
+            ("Sha512", "SHA512"),
+        ] {
+            let account = Account::from_otpauth_uri(&format!(
+                "otpauth://totp/github?secret=JBSWY3DPEHPK3PXP&algorithm={}",
+                value
+            ))
+            .unwrap();
+            assert_eq!(account.algorithm, expected);
+        }
+    }
+
+    #[test]
+    fn test_from_otpauth_uri_unknown_algorithm() {
+        let result = Account::from_otpauth_uri(
+            "otpauth://totp/github?secret=JBSWY3DPEHPK3PXP&algorithm=MD5",
+        );
+        assert!(result.is_err());
+    }
+
+    #[test]
+    fn test_from_otpauth_uri_missing_secret() {
+        let result = Account::from_otpauth_uri("otpauth://totp/github?issuer=GitHub");
+        assert!(result.is_err());
+    }
+
+    #[test]
+    fn test_from_otpauth_uri_issuer_sources() {
+        // Label prefix only
+        let account =
+            Account::from_otpauth_uri("otpauth://totp/GitHub:alice?secret=JBSWY3DPEHPK3PXP")
+                .unwrap();
+        assert_eq!(account.name, "alice");
+        assert_eq!(account.issuer, "GitHub");
+
+        // Query parameter only
+        let account =
+            Account::from_otpauth_uri("otpauth://totp/alice?secret=JBSWY3DPEHPK3PXP&issuer=GitHub")
+                .unwrap();
+        assert_eq!(account.name, "alice");
+        assert_eq!(account.issuer, "GitHub");
+
+        // Both, with the query parameter winning when they disagree
+        let uri = "otpauth://totp/GitHub:alice?secret=JBSWY3DPEHPK3PXP&issuer=GitHub";
+        let account = Account::from_otpauth_uri(uri).unwrap();
+        assert_eq!(account.issuer, "GitHub");
+        assert_eq!(Account::label_issuer_mismatch(uri), None);
+        let uri = "otpauth://totp/Old%20Name:%20alice?secret=JBSWY3DPEHPK3PXP&issuer=New%20Name";
+        let account = Account::from_otpauth_uri(uri).unwrap();
+        assert_eq!(account.name, "alice");
+        assert_eq!(account.issuer, "New Name");
+        assert_eq!(
+            Account::label_issuer_mismatch(uri),
+            Some(("New Name".to_string(), "Old Name".to_string()))
+        );
+
+        // Only one of them isn't a mismatch
+        assert_eq!(
+            Account::label_issuer_mismatch("otpauth://totp/GitHub:alice?secret=JBSWY3DPEHPK3PXP"),
+            None
+        );
+    }
+
+    #[test]
+    fn test_from_otpauth_uri_decodes_label() {
+        let account =
+            Account::from_otpauth_uri("otpauth://totp/My%20Company:My%20Account?secret=ABC234")
+                .unwrap();
+        assert_eq!(account.name, "My Account");
+        assert_eq!(account.issuer, "My Company");
+        // Some apps encode the separating colon too
+        let account =
+            Account::from_otpauth_uri("otpauth://totp/My%20Company%3AMy%20Account?secret=ABC234")
+                .unwrap();
+        assert_eq!(account.name, "My Account");
+        assert_eq!(account.issuer, "My Company");
+
+        assert!(Account::from_otpauth_uri("invalid://uri").is_err());
+        assert!(Account::from_otpauth_uri("otpauth://hotp/Account?secret=ABC234").is_err());
+    }
+
+    #[test]
+    fn test_account_builder() {
+        let account = Account::builder("github", "jbsw y3dp ehpk 3pxp")
+            .issuer("GitHub")
+            .algorithm("sha-256")
+            .digits(8)
+            .period(60)
+            .note("work")
+            .build()
+            .unwrap();
+        assert_eq!(account.secret, "JBSWY3DPEHPK3PXP");
+        assert_eq!(account.issuer, "GitHub");
+        assert_eq!(account.algorithm, "SHA256");
+        assert_eq!(account.digits, 8);
+        assert_eq!(account.period, 60);
+        assert_eq!(account.note.as_deref(), Some("work"));
+
+        let builder = Account::builder("github", "JBSWY3DPEHPK3PXP");
+        assert!(matches!(
+            Account::builder("github", "not base32!").build(),
+            Err(AppError::InvalidSecret)
+        ));
+        assert!(builder.clone().algorithm("MD5").build().is_err());
+        assert!(builder.clone().digits(5).build().is_err());
+        assert!(builder.clone().period(0).build().is_err());
+        assert_eq!(
+            builder.algorithm("steam").digits(8).build().unwrap().digits,
+            5
+        );
+    }
+
+    #[test]
+    fn test_secret_serializes_as_plain_string() {
+        let account = Account::new("github".to_string(), "JBSWY3DPEHPK3PXP".to_string());
+        let json = serde_json::to_value(&account).unwrap();
+        assert_eq!(json["secret"], "JBSWY3DPEHPK3PXP");
+
+        let parsed: Account = serde_json::from_value(json).unwrap();
+        assert_eq!(parsed.secret, "JBSWY3DPEHPK3PXP");
+        assert_eq!(format!("{:?}", parsed.secret), "Secret(..)");
+
+        let mut secret = parsed.secret.clone();
+        secret.zeroize();
+        assert!(secret.is_empty());
+    }
+
+    #[test]
+    fn test_otpauth_uri_round_trip() {
+        let mut account = Account::new("alice smith".to_string(), "JBSWY3DPEHPK3PXP".to_string());
+        account.issuer = "Example Corp".to_string();
+        account.algorithm = "SHA512".to_string();
+        account.digits = 8;
+        account.period = 60;
+
+        let parsed = Account::from_otpauth_uri(&account.generate_uri()).unwrap();
+        assert_eq!(parsed.name, account.name);
+        assert_eq!(parsed.secret, account.secret);
+        assert_eq!(parsed.issuer, account.issuer);
+        assert_eq!(parsed.algorithm, account.algorithm);
+        assert_eq!(parsed.digits, account.digits);
+        assert_eq!(parsed.period, account.period);
+
+        account.algorithm = STEAM_ALGORITHM.to_string();
+        account.digits = 5;
+        let parsed = Account::from_otpauth_uri(&account.generate_uri()).unwrap();
+        assert_eq!(parsed.algorithm, STEAM_ALGORITHM);
+        assert_eq!(parsed.digits, 5);
+    }
+
+    #[test]
+    fn test_otpauth_uri_round_trip_with_special_characters() {
+        let account = Account::builder("work#1 & co: a?b", "JBSWY3DPEHPK3PXP")
+            .issuer("AT&T: Mobile?")
+            .build()
+            .unwrap();
+
+        let uri = account.generate_uri();
+        assert!(
+            uri.starts_with("otpauth://totp/AT%26T%3A%20Mobile%3F:work%231%20%26%20co%3A%20a%3Fb?"),
+            "{}",
+            uri
+        );
+        assert!(uri.contains("&issuer=AT%26T%3A%20Mobile%3F&"), "{}", uri);
+        let parsed = Account::from_otpauth_uri(&uri).unwrap();
+        assert_eq!(parsed.name, account.name);
+        assert_eq!(parsed.issuer, account.issuer);
+        assert_eq!(parsed.secret, account.secret);
+    }
+
     #[test]
     fn test_invalid_algorithm() {
         let mut account = create_test_account(TEST_SECRET_SHA1);
@@ -77,6 +77,16 @@ pub fn run_hotpot_command(args: &[&str]) -> Output {
     cmd.output().expect("Failed to execute hotpot command")
 }
 
+pub fn run_hotpot_with_env(args: &[&str], envs: &[(&str, &Path)]) -> Output {
+    let mut cmd = Command::new("cargo");
+    cmd.arg("run");
+    cmd.arg("--");
+    cmd.args(args);
+    cmd.envs(envs.iter().copied());
+
+    cmd.output().expect("Failed to execute hotpot command")
+}
+
 pub fn run_hotpot_with_input(args: &[&str], input: &str) -> Output {
     let mut cmd = Command::new("cargo");
     cmd.arg("run");
@@ -102,12 +112,13 @@ pub fn run_hotpot_with_input(args: &[&str], input: &str) -> Output {
 }
 
 pub fn assert_totp_valid(output: &str) {
-    // Extract the code from output like "Code for github: 123456"
+    // Extract the code from output like "Code for github: 123456 (expires in 8s)"
     let code = if let Some(colon_pos) = output.rfind(':') {
         output[colon_pos + 1..].trim()
     } else {
         output.trim()
     };
+    let code = code.split(" (expires in").next().unwrap_or(code);
 
     assert_eq!(
         code.len(),